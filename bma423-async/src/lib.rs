@@ -3,6 +3,9 @@
 mod register;
 
 use bitflags::bitflags;
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, i2c::I2c};
+#[cfg(not(feature = "blocking"))]
 use embedded_hal_async::{delay::DelayNs, i2c::I2c};
 
 pub const PRIMARY_ADDRESS: u8 = 0x18;
@@ -162,13 +165,13 @@ fn sensor_temperature_as_celsius(temp: u8) -> Option<i16> {
     }
 }
 
-// #[test]
-// fn test_temperature_sensor_celsius_conversion() {
-//     assert_eq!(Some(150), sensor_temperature_as_celsius(0x7f));
-//     assert_eq!(Some(23), sensor_temperature_as_celsius(0x00));
-//     assert_eq!(Some(-104), sensor_temperature_as_celsius(0x81));
-//     assert_eq!(None, sensor_temperature_as_celsius(0x80))
-// }
+#[test]
+fn test_temperature_sensor_celsius_conversion() {
+    assert_eq!(Some(150), sensor_temperature_as_celsius(0x7f));
+    assert_eq!(Some(23), sensor_temperature_as_celsius(0x00));
+    assert_eq!(Some(-104), sensor_temperature_as_celsius(0x81));
+    assert_eq!(None, sensor_temperature_as_celsius(0x80))
+}
 
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -211,6 +214,7 @@ impl<E> core::convert::From<E> for Error<E> {
     }
 }
 
+#[maybe_async::maybe_async]
 impl<I2C: I2c<Error = E>, E, D: DelayNs> BMA423<I2C, D> {
     pub fn new(address: u8, i2c: I2C, delay: D) -> Self {
         BMA423 {
@@ -576,12 +580,15 @@ fn join_feature_conf_data_address(asic_lsb: u8, asic_msb: u8) -> usize {
     (((asic_msb as usize) << 4) | ((asic_lsb as usize) & 0x0F)) * 2
 }
 
-// #[test]
-// fn test_split_join_feature_conf_data_address() {
-//     for orig in 0..(u8::MAX as usize) {
-//         let orig = orig * 2;
-//         let (lsb, msb) = split_feature_conf_data_address(orig);
-//         let joined = join_feature_conf_data_address(lsb, msb);
-//         assert_eq!(orig, joined);
-//     }
-// }
+#[test]
+fn test_split_join_feature_conf_data_address() {
+    for orig in 0..(u8::MAX as usize) {
+        let orig = orig * 2;
+        let (lsb, msb) = split_feature_conf_data_address(orig);
+        let joined = join_feature_conf_data_address(lsb, msb);
+        assert_eq!(orig, joined);
+    }
+}
+
+#[cfg(test)]
+mod tests;
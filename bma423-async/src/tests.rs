@@ -0,0 +1,172 @@
+extern crate std;
+
+use std::{vec, vec::Vec};
+
+use embedded_hal_mock::eh1::{
+    delay::NoopDelay,
+    i2c::{Mock as I2cMock, Transaction},
+};
+
+use crate::{
+    feature_offset, register, split_feature_conf_data_address, Error, PowerMode, BMA423,
+    CONFIG_FILE, FEATURE_RW_SIZE, FEATURE_SIZE, PRIMARY_ADDRESS,
+};
+
+/// Run a driver call to completion, whether the driver is async or blocking.
+#[cfg(not(feature = "blocking"))]
+macro_rules! run {
+    ($call:expr) => {
+        embassy_futures::block_on($call)
+    };
+}
+
+#[cfg(feature = "blocking")]
+macro_rules! run {
+    ($call:expr) => {
+        $call
+    };
+}
+
+fn write(data: &[u8]) -> Transaction {
+    Transaction::write(PRIMARY_ADDRESS, data.to_vec())
+}
+
+fn read(register: u8, data: &[u8]) -> Transaction {
+    Transaction::write_read(PRIMARY_ADDRESS, vec![register], data.to_vec())
+}
+
+fn set_feature_address(addr: usize) -> Vec<Transaction> {
+    let (lsb, msb) = split_feature_conf_data_address(addr);
+    vec![
+        write(&[register::RESERVED_REG_5B, lsb]),
+        write(&[register::RESERVED_REG_5C, msb]),
+    ]
+}
+
+/// The driver reads the feature address back after every chunk to move it forward.
+fn increment_feature_address(addr: usize) -> Vec<Transaction> {
+    let (lsb, msb) = split_feature_conf_data_address(addr);
+    let mut transactions = vec![
+        read(register::RESERVED_REG_5B, &[lsb]),
+        read(register::RESERVED_REG_5C, &[msb]),
+    ];
+    transactions.extend(set_feature_address(addr + FEATURE_RW_SIZE));
+    transactions
+}
+
+fn burst_write_features(start: usize, data: &[u8]) -> Vec<Transaction> {
+    let mut transactions = set_feature_address(start);
+    for (i, chunk) in data.chunks(FEATURE_RW_SIZE).enumerate() {
+        let mut bytes = vec![register::FEATURES_IN];
+        bytes.extend_from_slice(chunk);
+        transactions.push(Transaction::write(PRIMARY_ADDRESS, bytes));
+        transactions.extend(increment_feature_address(start + i * FEATURE_RW_SIZE));
+    }
+    transactions
+}
+
+fn burst_read_features(start: usize, data: &[u8]) -> Vec<Transaction> {
+    let mut transactions = set_feature_address(start);
+    for (i, chunk) in data.chunks(FEATURE_RW_SIZE).enumerate() {
+        transactions.push(read(register::FEATURES_IN, chunk));
+        transactions.extend(increment_feature_address(start + i * FEATURE_RW_SIZE));
+    }
+    transactions
+}
+
+/// Run the driver against a bus that expects exactly `expectations`.
+fn with_bma423(expectations: &[Transaction], f: impl FnOnce(&mut BMA423<I2cMock, NoopDelay>)) {
+    let mut i2c = I2cMock::new(expectations);
+    let mut bma423 = BMA423::new(PRIMARY_ADDRESS, i2c.clone(), NoopDelay::new());
+    f(&mut bma423);
+    i2c.done();
+}
+
+fn initialization_start() -> Vec<Transaction> {
+    let mut transactions = vec![
+        read(register::CHIP_ID, &[0x13]),
+        write(&[register::PWR_CONF, 0x00]),
+        write(&[register::INIT_CTRL, 0x00]),
+    ];
+    transactions.extend(burst_write_features(0, CONFIG_FILE));
+    transactions.push(write(&[register::INIT_CTRL, 0x01]));
+    transactions
+}
+
+#[test]
+fn test_initialize() {
+    let mut expectations = initialization_start();
+    expectations.extend([
+        read(register::INTERNAL_STATUS, &[0x00]),
+        read(register::INTERNAL_STATUS, &[0x00]),
+        read(register::INTERNAL_STATUS, &[0x01]),
+        write(&[register::PWR_CONF, PowerMode::ADVANCED_POWER_SAVE.bits()]),
+    ]);
+    with_bma423(&expectations, |bma423| run!(bma423.initialize()).unwrap());
+}
+
+#[test]
+fn test_initialize_timeout() {
+    let mut expectations = initialization_start();
+    // Polled every 20ms for 200ms.
+    expectations.extend((0..10).map(|_| read(register::INTERNAL_STATUS, &[0x00])));
+    with_bma423(&expectations, |bma423| {
+        assert!(matches!(
+            run!(bma423.initialize()),
+            Err(Error::ASICInitialization)
+        ))
+    });
+}
+
+#[test]
+fn test_initialize_invalid_chip_id() {
+    let expectations = [read(register::CHIP_ID, &[0x12])];
+    with_bma423(&expectations, |bma423| {
+        assert!(matches!(
+            run!(bma423.initialize()),
+            Err(Error::InvalidChipId(0x12))
+        ))
+    });
+}
+
+#[test]
+fn test_reset_step_counter() {
+    let features: Vec<u8> = (0..FEATURE_SIZE as u8).collect();
+    let mut updated = features.clone();
+    updated[feature_offset::STEP_COUNTER_SETTINGS_26 + 1] |= 0b100;
+
+    let mut expectations = vec![
+        read(register::PWR_CONF, &[PowerMode::ADVANCED_POWER_SAVE.bits()]),
+        write(&[register::PWR_CONF, 0x00]),
+    ];
+    expectations.extend(burst_read_features(feature_offset::START, &features));
+    expectations.extend(burst_write_features(feature_offset::START, &updated));
+    expectations.push(write(&[
+        register::PWR_CONF,
+        PowerMode::ADVANCED_POWER_SAVE.bits(),
+    ]));
+
+    with_bma423(&expectations, |bma423| {
+        run!(bma423.reset_step_counter()).unwrap()
+    });
+}
+
+#[test]
+fn test_temperature_celsius() {
+    let expectations = [
+        read(register::TEMPERATURE, &[0x05]),
+        read(register::TEMPERATURE, &[0x80]),
+    ];
+    with_bma423(&expectations, |bma423| {
+        assert_eq!(run!(bma423.temperature_celsius()).unwrap(), Some(28));
+        assert_eq!(run!(bma423.temperature_celsius()).unwrap(), None);
+    });
+}
+
+#[test]
+fn test_step_count() {
+    let expectations = [read(register::STEP_COUNTER_0, &[0x39, 0x30, 0x00, 0x00])];
+    with_bma423(&expectations, |bma423| {
+        assert_eq!(run!(bma423.step_count()).unwrap(), 12345);
+    });
+}
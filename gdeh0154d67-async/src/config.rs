@@ -0,0 +1,273 @@
+/// VGH, the voltage that turns the gates on, for Gate Driving Voltage Control (0x03).
+/// `_20V` is the value after a reset.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateDrivingVoltage {
+    _10V = 0x03,
+    _10_5V = 0x04,
+    _11V = 0x05,
+    _11_5V = 0x06,
+    _12V = 0x07,
+    _12_5V = 0x08,
+    _13V = 0x09,
+    _13_5V = 0x0a,
+    _14V = 0x0b,
+    _14_5V = 0x0c,
+    _15V = 0x0d,
+    _15_5V = 0x0e,
+    _16V = 0x0f,
+    _16_5V = 0x10,
+    _17V = 0x11,
+    _17_5V = 0x12,
+    _18V = 0x13,
+    _18_5V = 0x14,
+    _19V = 0x15,
+    _19_5V = 0x16,
+    _20V = 0x17,
+}
+
+/// VSH1 or VSH2, the positive voltages that drive the source lines.
+/// Steps are 0.1 V up to 8.8 V and 0.2 V from 9 V.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceVoltageHigh {
+    _2_4V = 0x8e,
+    _2_5V = 0x8f,
+    _2_6V = 0x90,
+    _2_7V = 0x91,
+    _2_8V = 0x92,
+    _2_9V = 0x93,
+    _3V = 0x94,
+    _3_1V = 0x95,
+    _3_2V = 0x96,
+    _3_3V = 0x97,
+    _3_4V = 0x98,
+    _3_5V = 0x99,
+    _3_6V = 0x9a,
+    _3_7V = 0x9b,
+    _3_8V = 0x9c,
+    _3_9V = 0x9d,
+    _4V = 0x9e,
+    _4_1V = 0x9f,
+    _4_2V = 0xa0,
+    _4_3V = 0xa1,
+    _4_4V = 0xa2,
+    _4_5V = 0xa3,
+    _4_6V = 0xa4,
+    _4_7V = 0xa5,
+    _4_8V = 0xa6,
+    _4_9V = 0xa7,
+    _5V = 0xa8,
+    _5_1V = 0xa9,
+    _5_2V = 0xaa,
+    _5_3V = 0xab,
+    _5_4V = 0xac,
+    _5_5V = 0xad,
+    _5_6V = 0xae,
+    _5_7V = 0xaf,
+    _5_8V = 0xb0,
+    _5_9V = 0xb1,
+    _6V = 0xb2,
+    _6_1V = 0xb3,
+    _6_2V = 0xb4,
+    _6_3V = 0xb5,
+    _6_4V = 0xb6,
+    _6_5V = 0xb7,
+    _6_6V = 0xb8,
+    _6_7V = 0xb9,
+    _6_8V = 0xba,
+    _6_9V = 0xbb,
+    _7V = 0xbc,
+    _7_1V = 0xbd,
+    _7_2V = 0xbe,
+    _7_3V = 0xbf,
+    _7_4V = 0xc0,
+    _7_5V = 0xc1,
+    _7_6V = 0xc2,
+    _7_7V = 0xc3,
+    _7_8V = 0xc4,
+    _7_9V = 0xc5,
+    _8V = 0xc6,
+    _8_1V = 0xc7,
+    _8_2V = 0xc8,
+    _8_3V = 0xc9,
+    _8_4V = 0xca,
+    _8_5V = 0xcb,
+    _8_6V = 0xcc,
+    _8_7V = 0xcd,
+    _8_8V = 0xce,
+    _9V = 0x23,
+    _9_2V = 0x24,
+    _9_4V = 0x25,
+    _9_6V = 0x26,
+    _9_8V = 0x27,
+    _10V = 0x28,
+    _10_2V = 0x29,
+    _10_4V = 0x2a,
+    _10_6V = 0x2b,
+    _10_8V = 0x2c,
+    _11V = 0x2d,
+    _11_2V = 0x2e,
+    _11_4V = 0x2f,
+    _11_6V = 0x30,
+    _11_8V = 0x31,
+    _12V = 0x32,
+    _12_2V = 0x33,
+    _12_4V = 0x34,
+    _12_6V = 0x35,
+    _12_8V = 0x36,
+    _13V = 0x37,
+    _13_2V = 0x38,
+    _13_4V = 0x39,
+    _13_6V = 0x3a,
+    _13_8V = 0x3b,
+    _14V = 0x3c,
+    _14_2V = 0x3d,
+    _14_4V = 0x3e,
+    _14_6V = 0x3f,
+    _14_8V = 0x40,
+    _15V = 0x41,
+    _15_2V = 0x42,
+    _15_4V = 0x43,
+    _15_6V = 0x44,
+    _15_8V = 0x45,
+    _16V = 0x46,
+    _16_2V = 0x47,
+    _16_4V = 0x48,
+    _16_6V = 0x49,
+    _16_8V = 0x4a,
+    _17V = 0x4b,
+}
+
+/// VSL, the negative voltage that drives the source lines.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceVoltageLow {
+    Neg5V = 0x0a,
+    Neg5_5V = 0x0c,
+    Neg6V = 0x0e,
+    Neg6_5V = 0x10,
+    Neg7V = 0x12,
+    Neg7_5V = 0x14,
+    Neg8V = 0x16,
+    Neg8_5V = 0x18,
+    Neg9V = 0x1a,
+    Neg9_5V = 0x1c,
+    Neg10V = 0x1e,
+    Neg10_5V = 0x20,
+    Neg11V = 0x22,
+    Neg11_5V = 0x24,
+    Neg12V = 0x26,
+    Neg12_5V = 0x28,
+    Neg13V = 0x2a,
+    Neg13_5V = 0x2c,
+    Neg14V = 0x2e,
+    Neg14_5V = 0x30,
+    Neg15V = 0x32,
+    Neg15_5V = 0x34,
+    Neg16V = 0x36,
+    Neg16_5V = 0x38,
+    Neg17V = 0x3a,
+}
+
+/// Voltages for Source Driving Voltage Control (0x04).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceDrivingVoltage {
+    pub vsh1: SourceVoltageHigh,
+    pub vsh2: SourceVoltageHigh,
+    pub vsl: SourceVoltageLow,
+}
+
+impl SourceDrivingVoltage {
+    pub(crate) fn to_bytes(self) -> [u8; 3] {
+        [self.vsh1 as u8, self.vsh2 as u8, self.vsl as u8]
+    }
+}
+
+/// Voltage of the common electrode, for Write VCOM Register (0x2c).
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vcom {
+    Neg0_2V = 0x08,
+    Neg0_3V = 0x0c,
+    Neg0_4V = 0x10,
+    Neg0_5V = 0x14,
+    Neg0_6V = 0x18,
+    Neg0_7V = 0x1c,
+    Neg0_8V = 0x20,
+    Neg0_9V = 0x24,
+    Neg1V = 0x28,
+    Neg1_1V = 0x2c,
+    Neg1_2V = 0x30,
+    Neg1_3V = 0x34,
+    Neg1_4V = 0x38,
+    Neg1_5V = 0x3c,
+    Neg1_6V = 0x40,
+    Neg1_7V = 0x44,
+    Neg1_8V = 0x48,
+    Neg1_9V = 0x4c,
+    Neg2V = 0x50,
+    Neg2_1V = 0x54,
+    Neg2_2V = 0x58,
+    Neg2_3V = 0x5c,
+    Neg2_4V = 0x60,
+    Neg2_5V = 0x64,
+    Neg2_6V = 0x68,
+    Neg2_7V = 0x6c,
+    Neg2_8V = 0x70,
+    Neg2_9V = 0x74,
+    Neg3V = 0x78,
+}
+
+/// Number of dummy lines added to every frame, for Set Dummy Line Period (0x3a).
+/// More dummy lines make frames longer, which slows down refreshes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DummyLinePeriod {
+    lines: u8,
+}
+
+impl DummyLinePeriod {
+    /// Up to 127 lines.
+    pub const fn new(lines: u8) -> Option<Self> {
+        if lines > 0x7f {
+            return None;
+        }
+
+        Some(DummyLinePeriod { lines })
+    }
+
+    pub const fn lines(self) -> u8 {
+        self.lines
+    }
+
+    pub(crate) fn to_byte(self) -> u8 {
+        self.lines
+    }
+}
+
+/// How long each gate line is driven, for Set Gate Line Width (0x3b).
+/// Together with [DummyLinePeriod] this sets the frame rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GateLineWidth {
+    setting: u8,
+}
+
+impl GateLineWidth {
+    /// Setting from 0 to 15, where higher settings are longer. See the
+    /// datasheet of the controller for how long each one is.
+    pub const fn new(setting: u8) -> Option<Self> {
+        if setting > 0x0f {
+            return None;
+        }
+
+        Some(GateLineWidth { setting })
+    }
+
+    pub const fn setting(self) -> u8 {
+        self.setting
+    }
+
+    pub(crate) fn to_byte(self) -> u8 {
+        self.setting
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{
+    GateDrivingVoltage, LutVoltages, SourceDrivingVoltage, SourceVoltageHigh, SourceVoltageLow,
+    Vcom, WaveformLut, LUT_SIZE,
+};
+
+/// 4-level grayscale waveform, adapted from the one Waveshare ships for
+/// their SSD1680 panels. LUTn drives pixels with `(RED << 1) | BW == n`,
+/// which [gray2_plane_byte] maps to gray level `3 - n` (LUT0 is white).
+const GRAY2_LUT: [u8; LUT_SIZE] = [
+    // VS LUT0 to LUT4
+    0x00, 0x60, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x20, 0x60, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x28, 0x60, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x2a, 0x60, 0x15, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x00, 0x90, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    // TP, SR and RP of groups 0 to 11
+    0x00, 0x02, 0x00, 0x05, 0x14, 0x00, 0x00, //
+    0x1e, 0x1e, 0x00, 0x00, 0x00, 0x00, 0x01, //
+    0x00, 0x02, 0x00, 0x05, 0x14, 0x00, 0x00, //
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+    // FR
+    0x24, 0x22, 0x22, 0x22, 0x23, 0x32, //
+    // XON
+    0x00, 0x00, 0x00,
+];
+
+impl WaveformLut {
+    /// Waveform to use with [crate::GDEH0154D67::update_gray2].
+    pub fn gray2() -> Self {
+        WaveformLut::from_bytes(&GRAY2_LUT)
+    }
+}
+
+impl LutVoltages {
+    /// Voltages to use together with [WaveformLut::gray2].
+    pub fn gray2() -> Self {
+        LutVoltages {
+            end_option: 0x22,
+            gate: GateDrivingVoltage::_20V,
+            source: SourceDrivingVoltage {
+                vsh1: SourceVoltageHigh::_15V,
+                vsh2: SourceVoltageHigh::_5_6V,
+                vsl: SourceVoltageLow::Neg15V,
+            },
+            vcom: Vcom::Neg1V,
+        }
+    }
+}
+
+/// Which bit of a 2-bit pixel goes in which RAM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Gray2Plane {
+    Red = 1,
+    Bw = 0,
+}
+
+/// Extract 8 pixels of one bit plane from two bytes of a 2-bit image.
+///
+/// Pixels are packed 4 per byte starting from the most significant bits,
+/// with 0 being black and 3 white. The levels are inverted so that white
+/// pixels end up as 0 in both RAMs and select LUT0.
+pub(crate) fn gray2_plane_byte(pixels: &[u8], plane: Gray2Plane) -> u8 {
+    let mut byte = 0;
+
+    for i in 0..8 {
+        let Some(&packed) = pixels.get(i / 4) else {
+            break;
+        };
+        let level = (packed >> (6 - (i % 4) * 2)) & 0b11;
+        let bit = (!level >> plane as u8) & 1;
+        byte |= bit << (7 - i);
+    }
+
+    byte
+}
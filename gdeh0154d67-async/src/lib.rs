@@ -1,21 +1,52 @@
 #![no_std]
+// The state transitions return the whole driver type, which only counts as
+// complex once maybe-async takes the future around it away.
+#![cfg_attr(feature = "blocking", allow(clippy::type_complexity))]
 
-use core::convert::Infallible;
+mod config;
+mod gray;
+mod lut;
+mod panel_info;
+
+use core::{convert::Infallible, marker::PhantomData};
 
 use bitflags::bitflags;
 use embedded_hal::digital::{InputPin, OutputPin};
+#[cfg(feature = "blocking")]
+use embedded_hal::{delay::DelayNs, spi::SpiBus};
+#[cfg(not(feature = "blocking"))]
 use embedded_hal_async::{delay::DelayNs, digital::Wait, spi::SpiBus};
+use gray::{gray2_plane_byte, Gray2Plane};
+use panel_info::{DISPLAY_OPTION_SIZE, USER_ID_SIZE};
 use unwrap_infallible::UnwrapInfallible;
 
+pub use config::{
+    DummyLinePeriod, GateDrivingVoltage, GateLineWidth, SourceDrivingVoltage, SourceVoltageHigh,
+    SourceVoltageLow, Vcom,
+};
+pub use lut::{
+    LutGroup, LutVoltage, LutVoltages, WaveformLut, LUT_GROUPS, LUT_SIZE, LUT_VOLTAGE_TABLES,
+};
+pub use panel_info::{PanelInfo, PanelStatus, VciLevel};
+
 #[derive(Debug)]
 pub enum Error<E> {
     Spi(E),
+    BusyTimeout,
+    /// Reading from the display was attempted without enabling it with
+    /// [GDEH0154D67::set_bus_readable].
+    ReadUnsupported,
+    /// An image buffer doesn't match the size of the area it's drawn to.
+    BufferSize,
 }
 
 impl<E: core::fmt::Display> core::fmt::Display for Error<E> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Error::Spi(e) => write!(f, "Bus error: {}", e),
+            Error::BusyTimeout => write!(f, "Timed out waiting for the display"),
+            Error::ReadUnsupported => write!(f, "Reading from the display is not enabled"),
+            Error::BufferSize => write!(f, "Image buffer doesn't match the display area"),
         }
     }
 }
@@ -28,18 +59,39 @@ impl<E> From<E> for Error<E> {
 
 mod command {
     pub const DRIVER_OUTPUT_CONTROL: u8 = 0x01;
+    pub const GATE_DRIVING_VOLTAGE_CONTROL: u8 = 0x03;
+    pub const SOURCE_DRIVING_VOLTAGE_CONTROL: u8 = 0x04;
     pub const BOOSTER_SOFT_START_CONTROL: u8 = 0x0c;
     pub const DEEP_SLEEP_MODE: u8 = 0x10;
     pub const DATA_ENTRY_MODE_SETTING: u8 = 0x11;
     pub const SW_RESET: u8 = 0x12;
+    pub const HV_READY_DETECTION: u8 = 0x14;
+    pub const VCI_DETECTION: u8 = 0x15;
     pub const TEMPERATURE_SENSOR_CONTROL: u8 = 0x18;
+    pub const WRITE_TEMPERATURE_REGISTER: u8 = 0x1a;
+    pub const READ_TEMPERATURE_REGISTER: u8 = 0x1b;
     pub const MASTER_ACTIVATION: u8 = 0x20;
     pub const DISPLAY_UPDATE_CONTROL_1: u8 = 0x21;
     pub const DISPLAY_UPDATE_CONTROL_2: u8 = 0x22;
     pub const WRITE_RAM_BW: u8 = 0x24;
+    pub const WRITE_RAM_RED: u8 = 0x26;
+    pub const READ_RAM: u8 = 0x27;
+    pub const WRITE_VCOM_REGISTER: u8 = 0x2c;
+    pub const OTP_READ_DISPLAY_OPTION: u8 = 0x2d;
+    pub const USER_ID_READ: u8 = 0x2e;
+    pub const STATUS_BIT_READ: u8 = 0x2f;
+    pub const WRITE_LUT_REGISTER: u8 = 0x32;
+    pub const CRC_CALCULATION: u8 = 0x34;
+    pub const CRC_STATUS_READ: u8 = 0x35;
+    pub const SET_DUMMY_LINE_PERIOD: u8 = 0x3a;
+    pub const SET_GATE_LINE_WIDTH: u8 = 0x3b;
     pub const BORDER_WAVEFORM_CONTROL: u8 = 0x3c;
+    pub const END_OPTION: u8 = 0x3f;
+    pub const READ_RAM_OPTION: u8 = 0x41;
     pub const SET_RAM_X_START_END_POSITION: u8 = 0x44;
     pub const SET_RAM_Y_START_END_POSITION: u8 = 0x45;
+    pub const AUTO_WRITE_RED_RAM: u8 = 0x46;
+    pub const AUTO_WRITE_BW_RAM: u8 = 0x47;
     pub const SET_RAM_X_ADDRESS_POSITION: u8 = 0x4e;
     pub const SET_RAM_Y_ADDRESS_POSITION: u8 = 0x4f;
 }
@@ -84,6 +136,59 @@ pub enum RamOptions {
     Invert = 0b1000,
 }
 
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RamPlane {
+    /// The RAM that holds the image to draw
+    Bw = 0,
+
+    /// The RAM that holds the previous image during partial updates,
+    /// or the second bit plane in grayscale mode
+    Red = 1,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Size in pixels of the cells of a [Pattern]
+pub enum PatternStep {
+    _8 = 0b000,
+    _16 = 0b001,
+    _32 = 0b010,
+    _64 = 0b011,
+    _128 = 0b100,
+    _200 = 0b101,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Checkerboard pattern that the controller can write to a whole RAM by itself.
+pub struct Pattern {
+    /// Value of the bits in the top left cell. Cells alternate from there.
+    pub first_value: bool,
+    pub step_height: PatternStep,
+    pub step_width: PatternStep,
+}
+
+impl Pattern {
+    /// Fill the RAM with 1 bits, which are drawn as white with [RamOptions::Normal].
+    pub const WHITE: Pattern = Pattern::solid(true);
+
+    /// Fill the RAM with 0 bits, which are drawn as black with [RamOptions::Normal].
+    pub const BLACK: Pattern = Pattern::solid(false);
+
+    /// A single cell as big as the display.
+    pub const fn solid(value: bool) -> Self {
+        Pattern {
+            first_value: value,
+            step_height: PatternStep::_200,
+            step_width: PatternStep::_200,
+        }
+    }
+
+    pub(crate) fn to_byte(self) -> u8 {
+        (self.first_value as u8) << 7 | (self.step_height as u8) << 4 | self.step_width as u8
+    }
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PhaseDuration {
@@ -157,8 +262,13 @@ impl BoosterConfig {
 }
 
 bitflags! {
+    /// Direction in which the RAM address counter moves while image data is written.
+    ///
+    /// The X counter addresses bytes of 8 pixels, so leaving out
+    /// [Self::X_INCREMENT] reverses the order of the bytes in a row
+    /// but not the order of the pixels within each byte.
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-    struct DataEntryMode : u8 {
+    pub struct DataEntryMode : u8 {
         const X_INCREMENT = 1;
         const Y_INCREMENT = 1 << 1;
         const ADDR_MODE_Y = 1 << 2;
@@ -220,23 +330,113 @@ bitflags! {
     }
 }
 
+/// Encode a temperature for the temperature register, which holds
+/// a 12-bit two's complement value in 1/16 °C, left-aligned in 2 bytes.
+fn temperature_to_bytes(celsius: i16) -> [u8; 2] {
+    let celsius = celsius.clamp(i8::MIN.into(), i8::MAX.into()) as i8;
+    [celsius as u8, 0]
+}
+
+/// Decode the temperature register, dropping the fraction.
+fn temperature_from_bytes(bytes: [u8; 2]) -> i16 {
+    bytes[0] as i8 as i16
+}
+
+/// Number of bytes the controller expects for a RAM window, which covers
+/// every byte that a pixel of the window falls into.
+fn ram_window_len(x: u16, width: u16, height: u16) -> usize {
+    if width == 0 {
+        return 0;
+    }
+    let bytes_per_row = (x + width - 1) / 8 - x / 8 + 1;
+    bytes_per_row as usize * height as usize
+}
+
 const WIDTH: u16 = 200;
 const HEIGHT: u16 = 200;
 
-pub struct GDEH0154D67<SPI, DC, RES, Busy, Delay> {
+/// A full refresh takes around 2 seconds, so this leaves plenty of margin.
+pub const DEFAULT_BUSY_TIMEOUT_MS: u32 = 5000;
+
+#[cfg(feature = "blocking")]
+const BUSY_POLL_INTERVAL_MS: u32 = 5;
+
+/// The display's busy pin. The driver waits for it to go low with [Wait],
+/// so the executor can sleep during a refresh.
+#[cfg(not(feature = "blocking"))]
+pub trait BusyPin: InputPin<Error = Infallible> + Wait {}
+
+#[cfg(not(feature = "blocking"))]
+impl<T: InputPin<Error = Infallible> + Wait> BusyPin for T {}
+
+/// The display's busy pin, which the blocking driver polls.
+#[cfg(feature = "blocking")]
+pub trait BusyPin: InputPin<Error = Infallible> {}
+
+#[cfg(feature = "blocking")]
+impl<T: InputPin<Error = Infallible>> BusyPin for T {}
+
+/// The display hasn't been initialized since it was powered on, or since
+/// we lost track of it because the MCU was reset.
+pub struct Uninitialized;
+
+/// The display can receive commands.
+pub struct Awake;
+
+/// The display can receive commands, but it woke up from [DeepSleepMode2]
+/// so its RAM holds garbage. A partial update would drive that garbage to the
+/// panel, so the RAM has to be overwritten before anything else is done.
+pub struct RamLost;
+
+/// The display is in deep sleep and must be woken up by a hardware reset
+/// before it can receive commands again.
+pub struct Hibernating<Mode: SleepMode>(PhantomData<Mode>);
+
+/// Deep sleep mode 1, the contents of the RAM are retained.
+pub struct DeepSleepMode1;
+
+/// Deep sleep mode 2, the contents of the RAM are lost.
+pub struct DeepSleepMode2;
+
+pub trait SleepMode {
+    const MODE: DeepSleepMode;
+    /// The state the display is in after waking up from this mode.
+    type Woken;
+}
+
+impl SleepMode for DeepSleepMode1 {
+    const MODE: DeepSleepMode = DeepSleepMode::RetainRAM;
+    type Woken = Awake;
+}
+
+impl SleepMode for DeepSleepMode2 {
+    const MODE: DeepSleepMode = DeepSleepMode::ResetRAM;
+    type Woken = RamLost;
+}
+
+/// Driver for the display. `State` tracks the power state of the panel,
+/// so that only the operations that make sense in that state are available.
+pub struct GDEH0154D67<SPI, DC, RES, Busy, Delay, State = Uninitialized> {
     spi: SPI,
     dc: DC,
     reset: RES,
     busy: Busy,
     delay: Delay,
+    busy_timeout_ms: u32,
+    bus_readable: bool,
+    using_partial_mode: bool,
+    temperature_written: bool,
+    data_entry_mode: DataEntryMode,
+    state: PhantomData<State>,
 }
 
-impl<SPI, DC, RES, Busy, Delay, E> GDEH0154D67<SPI, DC, RES, Busy, Delay>
+#[maybe_async::maybe_async]
+impl<SPI, DC, RES, Busy, Delay, E> GDEH0154D67<SPI, DC, RES, Busy, Delay, Uninitialized>
 where
     SPI: SpiBus<Error = E>,
     DC: OutputPin<Error = Infallible>,
     RES: OutputPin<Error = Infallible>,
-    Busy: InputPin<Error = Infallible> + Wait,
+    Busy: BusyPin,
     Delay: DelayNs,
 {
     pub fn new(
@@ -252,22 +452,36 @@ where
             reset: reset_pin,
             busy: busy_pin,
             delay,
+            busy_timeout_ms: DEFAULT_BUSY_TIMEOUT_MS,
+            bus_readable: false,
+            using_partial_mode: false,
+            temperature_written: false,
+            data_entry_mode: DataEntryMode::DEFAULT,
+            state: PhantomData,
         }
     }
 
-    // Operation flow
-
-    pub async fn init(&mut self) -> Result<(), Error<E>> {
+    /// Reset the display and get it ready to receive commands.
+    pub async fn init(mut self) -> Result<GDEH0154D67<SPI, DC, RES, Busy, Delay, Awake>, Error<E>> {
         // We have to wait 10ms after power is supplied.
         self.delay.delay_ms(10).await;
 
-        self.hardware_reset().await;
-        self.software_reset().await?;
-
-        self.set_driver_output().await?;
+        self.reset_and_initialize().await?;
 
-        Ok(())
+        Ok(self.into_state())
     }
+}
+
+#[maybe_async::maybe_async]
+impl<SPI, DC, RES, Busy, Delay, E> GDEH0154D67<SPI, DC, RES, Busy, Delay, Awake>
+where
+    SPI: SpiBus<Error = E>,
+    DC: OutputPin<Error = Infallible>,
+    RES: OutputPin<Error = Infallible>,
+    Busy: BusyPin,
+    Delay: DelayNs,
+{
+    // Operation flow
 
     pub async fn set_partial_ram_area(
         &mut self,
@@ -276,16 +490,53 @@ where
         width: u16,
         height: u16,
     ) -> Result<(), Error<E>> {
-        self.set_data_entry_mode(DataEntryMode::DEFAULT).await?;
+        self.set_data_entry_mode(self.data_entry_mode).await?;
+
+        let (x_start, x_end) = self.ram_window_x(x, width);
+        let (y_start, y_end) = self.ram_window_y(y, height);
+        self.set_ram_x_start_end_position(x_start, x_end).await?;
+        self.set_ram_y_start_end_position(y_start, y_end).await?;
+        self.set_ram_x_address_position(x_start).await?;
+        self.set_ram_y_address_position(y_start).await?;
+
+        Ok(())
+    }
 
-        self.set_ram_x_start_end_position(x, width).await?;
-        self.set_ram_y_start_end_position(y, height).await?;
-        self.set_ram_x_address_position(x).await?;
-        self.set_ram_y_address_position(y).await?;
+    /// Move the address counter back to the start of a window set with
+    /// [Self::set_partial_ram_area], to write the same window again.
+    async fn reset_ram_address(
+        &mut self,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+    ) -> Result<(), Error<E>> {
+        self.set_ram_x_address_position(self.ram_window_x(x, width).0)
+            .await?;
+        self.set_ram_y_address_position(self.ram_window_y(y, height).0)
+            .await?;
 
         Ok(())
     }
 
+    /// First and last X position of a window in the order the address counter visits them.
+    fn ram_window_x(&self, x: u16, width: u16) -> (u16, u16) {
+        if self.data_entry_mode.contains(DataEntryMode::X_INCREMENT) {
+            (x, x + width - 1)
+        } else {
+            (x + width - 1, x)
+        }
+    }
+
+    /// First and last Y position of a window in the order the address counter visits them.
+    fn ram_window_y(&self, y: u16, height: u16) -> (u16, u16) {
+        if self.data_entry_mode.contains(DataEntryMode::Y_INCREMENT) {
+            (y, y + height - 1)
+        } else {
+            (y + height - 1, y)
+        }
+    }
+
     pub async fn set_border_color(&mut self, color: BorderColor) -> Result<(), Error<E>> {
         self.set_border_waveform(color as u8).await
     }
@@ -299,10 +550,294 @@ where
         Ok(())
     }
 
+    /// Replace the waveform LUT loaded from OTP with a custom one.
+    /// Display update sequences that use it must not include
+    /// [DisplayUpdateSequence::LOAD_LUT], or it will be overwritten again.
+    pub async fn upload_lut(
+        &mut self,
+        lut: &WaveformLut,
+        voltages: LutVoltages,
+    ) -> Result<(), Error<E>> {
+        self.write_command_data(command::WRITE_LUT_REGISTER, &lut.to_bytes())
+            .await?;
+        self.write_command_data(command::END_OPTION, &[voltages.end_option])
+            .await?;
+        self.set_gate_driving_voltage(voltages.gate).await?;
+        self.set_source_driving_voltage(voltages.source).await?;
+        self.set_vcom(voltages.vcom).await?;
+
+        Ok(())
+    }
+
     pub async fn write_image_data(&mut self, data: &[u8]) -> Result<(), Error<E>> {
         self.write_bw_ram(data).await
     }
 
+    /// Write the frame that is currently shown on the display to the RED RAM.
+    /// During a partial update the controller compares it against the BW RAM
+    /// and only drives the pixels that changed.
+    pub async fn write_previous_image_data(&mut self, data: &[u8]) -> Result<(), Error<E>> {
+        self.write_red_ram(data).await
+    }
+
+    /// Draw a 4-level grayscale image to the whole display.
+    ///
+    /// `image` holds 4 pixels per byte starting from the most significant bits,
+    /// with 0 being black and 3 white, like `embedded_graphics`' `Gray2`.
+    /// The image is split into two bit planes that are written to the RED and BW
+    /// RAMs, then `lut` drives each of the four combinations to a different level.
+    /// Returns [Error::BufferSize] unless `image` covers the whole display.
+    pub async fn update_gray2(
+        &mut self,
+        image: &[u8],
+        lut: &WaveformLut,
+        voltages: LutVoltages,
+    ) -> Result<(), Error<E>> {
+        if image.len() != WIDTH as usize * HEIGHT as usize / 4 {
+            return Err(Error::BufferSize);
+        }
+
+        self.upload_lut(lut, voltages).await?;
+
+        self.set_partial_ram_area(0, 0, WIDTH, HEIGHT).await?;
+        self.write_gray2_plane(command::WRITE_RAM_RED, image, Gray2Plane::Red)
+            .await?;
+
+        self.reset_ram_address(0, 0, WIDTH, HEIGHT).await?;
+        self.write_gray2_plane(command::WRITE_RAM_BW, image, Gray2Plane::Bw)
+            .await?;
+
+        // Don't load the LUT from OTP or it will replace the one we just uploaded.
+        self.update_display(DisplayUpdateSequence::DRIVE_DISPLAY_PANEL, None)
+            .await
+    }
+
+    /// Refresh a window of the display without flashing the whole panel.
+    ///
+    /// `previous` is the image currently shown in the window and `current`
+    /// the one that should replace it. Both are laid out row by row with
+    /// one bit per pixel, so `x` and `width` should be multiples of 8.
+    /// Returns [Error::BufferSize] unless both hold exactly one window's
+    /// worth of bytes.
+    pub async fn update_partial(
+        &mut self,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        previous: &[u8],
+        current: &[u8],
+    ) -> Result<(), Error<E>> {
+        let len = ram_window_len(x, width, height);
+        if previous.len() != len || current.len() != len {
+            return Err(Error::BufferSize);
+        }
+
+        self.set_partial_ram_area(x, y, width, height).await?;
+        self.write_red_ram(previous).await?;
+
+        // Writing the RED RAM moved the address counter to the end of the window.
+        self.reset_ram_address(x, y, width, height).await?;
+        self.write_bw_ram(current).await?;
+
+        self.update_display(DisplayUpdateSequence::WATCHY_UPDATE_PARTIAL, None)
+            .await
+    }
+
+    /// Fill one of the RAMs with a pattern without sending the image over SPI.
+    pub async fn fill_ram(&mut self, plane: RamPlane, pattern: Pattern) -> Result<(), Error<E>> {
+        let command = match plane {
+            RamPlane::Bw => command::AUTO_WRITE_BW_RAM,
+            RamPlane::Red => command::AUTO_WRITE_RED_RAM,
+        };
+
+        self.set_partial_ram_area(0, 0, WIDTH, HEIGHT).await?;
+        self.write_command_data(command, &[pattern.to_byte()])
+            .await?;
+        self.busy_wait().await
+    }
+
+    /// Fill both RAMs with white. The display must be updated to show the change.
+    pub async fn clear(&mut self) -> Result<(), Error<E>> {
+        self.fill_ram(RamPlane::Bw, Pattern::WHITE).await?;
+        self.fill_ram(RamPlane::Red, Pattern::WHITE).await
+    }
+
+    /// Read the contents of one of the RAMs, starting from the top left corner.
+    pub async fn read_ram(&mut self, plane: RamPlane, buf: &mut [u8]) -> Result<(), Error<E>> {
+        if !self.bus_readable {
+            return Err(Error::ReadUnsupported);
+        }
+
+        self.set_partial_ram_area(0, 0, WIDTH, HEIGHT).await?;
+        self.write_command_data(command::READ_RAM_OPTION, &[plane as u8])
+            .await?;
+        self.write_command(command::READ_RAM).await?;
+
+        // The first byte read after the command is a dummy byte.
+        let mut dummy = [0];
+        self.read_data(&mut dummy).await?;
+        self.read_data(buf).await
+    }
+
+    /// Have the display calculate a CRC of its RAM. The value can be stored
+    /// after drawing a frame and compared later to check whether the frame
+    /// is still in RAM, without transferring it back.
+    pub async fn ram_crc(&mut self) -> Result<u16, Error<E>> {
+        if !self.bus_readable {
+            return Err(Error::ReadUnsupported);
+        }
+
+        self.write_command(command::CRC_CALCULATION).await?;
+        self.busy_wait().await?;
+
+        let mut crc = [0; 2];
+        self.write_command(command::CRC_STATUS_READ).await?;
+        self.read_data(&mut crc).await?;
+
+        Ok(u16::from_be_bytes(crc))
+    }
+
+    /// Read the status bits, including the results of the HV ready and VCI detections.
+    pub async fn read_status(&mut self) -> Result<PanelStatus, Error<E>> {
+        if !self.bus_readable {
+            return Err(Error::ReadUnsupported);
+        }
+
+        let mut status = [0];
+        self.write_command(command::STATUS_BIT_READ).await?;
+        self.read_data(&mut status).await?;
+
+        Ok(PanelStatus::from_bits_retain(status[0]))
+    }
+
+    /// Read what the controller knows about the panel, which tells panel
+    /// revisions apart and is worth including in bug reports.
+    pub async fn read_panel_info(&mut self) -> Result<PanelInfo, Error<E>> {
+        if !self.bus_readable {
+            return Err(Error::ReadUnsupported);
+        }
+
+        let mut display_option = [0; DISPLAY_OPTION_SIZE];
+        self.write_command(command::OTP_READ_DISPLAY_OPTION).await?;
+        self.read_data(&mut display_option).await?;
+
+        let mut user_id = [0; USER_ID_SIZE];
+        self.write_command(command::USER_ID_READ).await?;
+        self.read_data(&mut user_id).await?;
+
+        let status = self.read_status().await?;
+
+        Ok(PanelInfo::from_bytes(&display_option, user_id, status))
+    }
+
+    /// Check whether the booster manages to bring up the high voltages that
+    /// drive the panel, which fails when the supply is too weak.
+    pub async fn detect_hv_ready(&mut self) -> Result<bool, Error<E>> {
+        if !self.bus_readable {
+            return Err(Error::ReadUnsupported);
+        }
+
+        self.update_display(
+            DisplayUpdateSequence::ENABLE_CLOCK_SIGNAL | DisplayUpdateSequence::ENABLE_ANALOG,
+            None,
+        )
+        .await?;
+        // Shortest cool down duration, a single loop.
+        self.write_command_data(command::HV_READY_DETECTION, &[0x00])
+            .await?;
+        // The SSD1681 datasheet (commands 0x14 and 0x15) starts the detection
+        // as soon as the command is received and keeps BUSY high until the
+        // result is in the status bits, so no Master Activation is needed.
+        self.busy_wait().await?;
+
+        let status = self.read_status().await?;
+
+        self.update_display(
+            DisplayUpdateSequence::DISABLE_ANALOG | DisplayUpdateSequence::DISABLE_CLOCK_SIGNAL,
+            None,
+        )
+        .await?;
+
+        Ok(!status.contains(PanelStatus::HV_NOT_READY))
+    }
+
+    /// Check whether the supply voltage is at least `level`,
+    /// for example to skip a refresh that would fail on a flat battery.
+    pub async fn detect_vci(&mut self, level: VciLevel) -> Result<bool, Error<E>> {
+        if !self.bus_readable {
+            return Err(Error::ReadUnsupported);
+        }
+
+        self.update_display(DisplayUpdateSequence::ENABLE_CLOCK_SIGNAL, None)
+            .await?;
+        self.write_command_data(command::VCI_DETECTION, &[level as u8])
+            .await?;
+        // Like HV ready detection, this starts on its own.
+        self.busy_wait().await?;
+
+        let status = self.read_status().await?;
+
+        self.update_display(DisplayUpdateSequence::DISABLE_CLOCK_SIGNAL, None)
+            .await?;
+
+        Ok(!status.contains(PanelStatus::VCI_LOW))
+    }
+
+    /// Write the ambient temperature to the display's temperature register,
+    /// for example from a sensor that is closer to the outside than the
+    /// display's own. The controller uses it to pick a waveform from OTP.
+    ///
+    /// Until [Self::select_temperature_sensor] is called again,
+    /// [DisplayUpdateSequence::LOAD_TEMPERATURE_VALUE] is left out of
+    /// update sequences so that the value isn't overwritten.
+    pub async fn write_temperature(&mut self, celsius: i16) -> Result<(), Error<E>> {
+        self.write_command_data(
+            command::WRITE_TEMPERATURE_REGISTER,
+            &temperature_to_bytes(celsius),
+        )
+        .await?;
+        self.temperature_written = true;
+
+        Ok(())
+    }
+
+    /// Read the temperature register in whole degrees Celsius. It holds either
+    /// the last value passed to [Self::write_temperature], or the last value
+    /// loaded from the selected sensor during a display update.
+    pub async fn read_temperature(&mut self) -> Result<i16, Error<E>> {
+        if !self.bus_readable {
+            return Err(Error::ReadUnsupported);
+        }
+
+        let mut temperature = [0; 2];
+        self.write_command(command::READ_TEMPERATURE_REGISTER)
+            .await?;
+        self.read_data(&mut temperature).await?;
+
+        Ok(temperature_from_bytes(temperature))
+    }
+
+    /// Have the display measure the temperature with its internal sensor
+    /// and read back the result in whole degrees Celsius.
+    pub async fn measure_temperature(&mut self) -> Result<i16, Error<E>> {
+        if !self.bus_readable {
+            return Err(Error::ReadUnsupported);
+        }
+
+        self.select_temperature_sensor(TemperatureSensor::Internal)
+            .await?;
+        self.update_display(
+            DisplayUpdateSequence::ENABLE_CLOCK_SIGNAL
+                | DisplayUpdateSequence::LOAD_TEMPERATURE_VALUE
+                | DisplayUpdateSequence::DISABLE_CLOCK_SIGNAL,
+            None,
+        )
+        .await?;
+
+        self.read_temperature().await
+    }
+
     /// Update the display with the contents of the RAM.
     pub async fn update_display(
         &mut self,
@@ -318,29 +853,30 @@ where
         Ok(())
     }
 
-    pub async fn hibernate(&mut self) -> Result<(), Error<E>> {
-        self.set_deep_sleep_mode(DeepSleepMode::RetainRAM).await
+    /// Put the display in deep sleep mode 1. The contents of the RAM are retained.
+    pub async fn hibernate(
+        self,
+    ) -> Result<GDEH0154D67<SPI, DC, RES, Busy, Delay, Hibernating<DeepSleepMode1>>, Error<E>> {
+        self.enter_deep_sleep().await
     }
 
-    // Commands
-
-    async fn hardware_reset(&mut self) {
-        self.reset.set_low().unwrap_infallible();
-        self.delay.delay_ms(10).await;
-        self.reset.set_high().unwrap_infallible();
-        self.delay.delay_ms(10).await;
+    /// Put the display in deep sleep mode 2, which uses less power
+    /// but loses the contents of the RAM.
+    pub async fn hibernate_and_reset_ram(
+        self,
+    ) -> Result<GDEH0154D67<SPI, DC, RES, Busy, Delay, Hibernating<DeepSleepMode2>>, Error<E>> {
+        self.enter_deep_sleep().await
     }
 
-    /// Resets the commands and parameters to their S/W Reset default values
-    /// except Deep Sleep Mode.
-    /// RAM is unaffected by this command.
-    async fn software_reset(&mut self) -> Result<(), Error<E>> {
-        self.write_command(command::SW_RESET).await?;
-        // According to the SSD1681 spec
-        self.delay.delay_ms(10).await;
-        Ok(())
+    async fn enter_deep_sleep<Mode: SleepMode>(
+        mut self,
+    ) -> Result<GDEH0154D67<SPI, DC, RES, Busy, Delay, Hibernating<Mode>>, Error<E>> {
+        self.set_deep_sleep_mode(Mode::MODE).await?;
+        Ok(self.into_state())
     }
 
+    // Commands
+
     pub async fn booster_soft_start_control(
         &mut self,
         config: BoosterConfig,
@@ -349,13 +885,35 @@ where
             .await
     }
 
-    async fn set_driver_output(&mut self) -> Result<(), Error<E>> {
-        // The first 9 bits set the number of vertical rows that the display has,
-        // the first 3 bits of the last byte set the gate scanning sequence and direction.
-        // Probably not a good idea to mess with this.
-        self.write_command_data(command::DRIVER_OUTPUT_CONTROL, &[0xc7, 0x00, 0x00])
-            .await?;
-        Ok(())
+    pub async fn set_gate_driving_voltage(
+        &mut self,
+        voltage: GateDrivingVoltage,
+    ) -> Result<(), Error<E>> {
+        self.write_command_data(command::GATE_DRIVING_VOLTAGE_CONTROL, &[voltage as u8])
+            .await
+    }
+
+    pub async fn set_source_driving_voltage(
+        &mut self,
+        voltage: SourceDrivingVoltage,
+    ) -> Result<(), Error<E>> {
+        self.write_command_data(command::SOURCE_DRIVING_VOLTAGE_CONTROL, &voltage.to_bytes())
+            .await
+    }
+
+    pub async fn set_vcom(&mut self, vcom: Vcom) -> Result<(), Error<E>> {
+        self.write_command_data(command::WRITE_VCOM_REGISTER, &[vcom as u8])
+            .await
+    }
+
+    pub async fn set_dummy_line_period(&mut self, period: DummyLinePeriod) -> Result<(), Error<E>> {
+        self.write_command_data(command::SET_DUMMY_LINE_PERIOD, &[period.to_byte()])
+            .await
+    }
+
+    pub async fn set_gate_line_width(&mut self, width: GateLineWidth) -> Result<(), Error<E>> {
+        self.write_command_data(command::SET_GATE_LINE_WIDTH, &[width.to_byte()])
+            .await
     }
 
     /// Set how the X and Y coordinates are incremented while drawing to the display.
@@ -369,24 +927,24 @@ where
     }
 
     /// Set the horizontal window in display RAM where image data will be written.
-    async fn set_ram_x_start_end_position(&mut self, x: u16, width: u16) -> Result<(), Error<E>> {
+    async fn set_ram_x_start_end_position(&mut self, start: u16, end: u16) -> Result<(), Error<E>> {
         self.write_command_data(
             command::SET_RAM_X_START_END_POSITION,
-            &[(x / 8) as u8, ((x + width - 1) / 8) as u8],
+            &[(start / 8) as u8, (end / 8) as u8],
         )
         .await?;
         Ok(())
     }
 
     /// Set the vertical window in display RAM where image data will be written.
-    async fn set_ram_y_start_end_position(&mut self, y: u16, height: u16) -> Result<(), Error<E>> {
+    async fn set_ram_y_start_end_position(&mut self, start: u16, end: u16) -> Result<(), Error<E>> {
         self.write_command_data(
             command::SET_RAM_Y_START_END_POSITION,
             &[
-                (y % 0xFF) as u8,
-                (y / 0xFF) as u8,
-                ((y + height - 1) % 0xFF) as u8,
-                ((y + height - 1) / 0xFF) as u8,
+                (start % 0xFF) as u8,
+                (start / 0xFF) as u8,
+                (end % 0xFF) as u8,
+                (end / 0xFF) as u8,
             ],
         )
         .await?;
@@ -424,7 +982,10 @@ where
         sensor: TemperatureSensor,
     ) -> Result<(), Error<E>> {
         self.write_command_data(command::TEMPERATURE_SENSOR_CONTROL, &[sensor as u8])
-            .await
+            .await?;
+        self.temperature_written = false;
+
+        Ok(())
     }
 
     async fn set_display_update_ram_options(
@@ -448,8 +1009,11 @@ where
     // 0 = disable clock signal
     async fn set_display_update_sequence(
         &mut self,
-        sequence: DisplayUpdateSequence,
+        mut sequence: DisplayUpdateSequence,
     ) -> Result<(), Error<E>> {
+        if self.temperature_written {
+            sequence.remove(DisplayUpdateSequence::LOAD_TEMPERATURE_VALUE);
+        }
         self.write_command_data(command::DISPLAY_UPDATE_CONTROL_2, &[sequence.bits()])
             .await?;
         Ok(())
@@ -457,23 +1021,218 @@ where
 
     async fn master_activation(&mut self) -> Result<(), Error<E>> {
         self.write_command(command::MASTER_ACTIVATION).await?;
-        self.busy_wait().await;
-        Ok(())
+        self.busy_wait().await
     }
 
     async fn write_bw_ram(&mut self, data: &[u8]) -> Result<(), Error<E>> {
         self.write_command_data(command::WRITE_RAM_BW, data).await
     }
 
+    async fn write_red_ram(&mut self, data: &[u8]) -> Result<(), Error<E>> {
+        self.write_command_data(command::WRITE_RAM_RED, data).await
+    }
+
+    async fn write_gray2_plane(
+        &mut self,
+        command: u8,
+        image: &[u8],
+        plane: Gray2Plane,
+    ) -> Result<(), Error<E>> {
+        self.write_command(command).await?;
+
+        let mut chunk = [0; 64];
+        for pixels in image.chunks(chunk.len() * 2) {
+            let len = pixels.len().div_ceil(2);
+            for (byte, pixels) in chunk.iter_mut().zip(pixels.chunks(2)) {
+                *byte = gray2_plane_byte(pixels, plane);
+            }
+            self.write_data(&chunk[..len]).await?;
+        }
+
+        Ok(())
+    }
+
     async fn set_deep_sleep_mode(&mut self, mode: DeepSleepMode) -> Result<(), Error<E>> {
         self.write_command_data(command::DEEP_SLEEP_MODE, &[mode as u8])
             .await
     }
+}
+
+#[maybe_async::maybe_async]
+impl<SPI, DC, RES, Busy, Delay, E, Mode> GDEH0154D67<SPI, DC, RES, Busy, Delay, Hibernating<Mode>>
+where
+    SPI: SpiBus<Error = E>,
+    DC: OutputPin<Error = Infallible>,
+    RES: OutputPin<Error = Infallible>,
+    Busy: BusyPin,
+    Delay: DelayNs,
+    Mode: SleepMode,
+{
+    /// Wake the display from deep sleep. If it was sleeping in [DeepSleepMode1]
+    /// the contents of the RAM are still there, otherwise it wakes up as
+    /// [RamLost].
+    pub async fn wake(
+        mut self,
+    ) -> Result<GDEH0154D67<SPI, DC, RES, Busy, Delay, Mode::Woken>, Error<E>> {
+        self.reset_and_initialize().await?;
+
+        Ok(self.into_state())
+    }
+}
+
+#[maybe_async::maybe_async]
+impl<SPI, DC, RES, Busy, Delay, E> GDEH0154D67<SPI, DC, RES, Busy, Delay, RamLost>
+where
+    SPI: SpiBus<Error = E>,
+    DC: OutputPin<Error = Infallible>,
+    RES: OutputPin<Error = Infallible>,
+    Busy: BusyPin,
+    Delay: DelayNs,
+{
+    /// Fill both RAMs with white. The display must be updated to show the change.
+    pub async fn clear(self) -> Result<GDEH0154D67<SPI, DC, RES, Busy, Delay, Awake>, Error<E>> {
+        let mut display = self.into_state::<Awake>();
+        display.clear().await?;
+
+        Ok(display)
+    }
+
+    /// Write an image of the whole display to both RAMs, so that it's both the
+    /// frame to show and the one a later partial update compares against.
+    /// The display must be updated to show it.
+    pub async fn write_frame(
+        self,
+        image: &[u8],
+    ) -> Result<GDEH0154D67<SPI, DC, RES, Busy, Delay, Awake>, Error<E>> {
+        if image.len() != ram_window_len(0, WIDTH, HEIGHT) {
+            return Err(Error::BufferSize);
+        }
+
+        let mut display = self.into_state::<Awake>();
+        display.set_partial_ram_area(0, 0, WIDTH, HEIGHT).await?;
+        display.write_bw_ram(image).await?;
+        display.reset_ram_address(0, 0, WIDTH, HEIGHT).await?;
+        display.write_red_ram(image).await?;
+
+        Ok(display)
+    }
+}
+
+#[maybe_async::maybe_async]
+impl<SPI, DC, RES, Busy, Delay, E, State> GDEH0154D67<SPI, DC, RES, Busy, Delay, State>
+where
+    SPI: SpiBus<Error = E>,
+    DC: OutputPin<Error = Infallible>,
+    RES: OutputPin<Error = Infallible>,
+    Busy: BusyPin,
+    Delay: DelayNs,
+{
+    /// Set how long to wait for the display to finish an operation before
+    /// giving up with [Error::BusyTimeout].
+    pub fn set_busy_timeout_ms(&mut self, timeout_ms: u32) {
+        self.busy_timeout_ms = timeout_ms;
+    }
+
+    /// Tell the driver whether data can be read from the display.
+    /// This requires the SDA line of the display to be connected to the MISO
+    /// pin of the SPI bus, or a 3-wire SPI bus. On the Watchy it isn't.
+    pub fn set_bus_readable(&mut self, readable: bool) {
+        self.bus_readable = readable;
+    }
+
+    /// Set the direction in which image data fills the RAM windows set with
+    /// [GDEH0154D67::set_partial_ram_area] from then on, for example to flip
+    /// the image upside down without touching the image data itself.
+    pub fn set_address_direction(&mut self, mode: DataEntryMode) {
+        self.data_entry_mode = mode;
+    }
+
+    fn into_state<NewState>(self) -> GDEH0154D67<SPI, DC, RES, Busy, Delay, NewState> {
+        GDEH0154D67 {
+            spi: self.spi,
+            dc: self.dc,
+            reset: self.reset,
+            busy: self.busy,
+            delay: self.delay,
+            busy_timeout_ms: self.busy_timeout_ms,
+            bus_readable: self.bus_readable,
+            // The panel needs to be powered on again after a reset.
+            using_partial_mode: false,
+            temperature_written: false,
+            data_entry_mode: self.data_entry_mode,
+            state: PhantomData,
+        }
+    }
+
+    async fn reset_and_initialize(&mut self) -> Result<(), Error<E>> {
+        self.hardware_reset().await;
+        self.software_reset().await?;
+
+        self.set_driver_output().await?;
+
+        Ok(())
+    }
+
+    // Commands
+
+    async fn hardware_reset(&mut self) {
+        self.reset.set_low().unwrap_infallible();
+        self.delay.delay_ms(10).await;
+        self.reset.set_high().unwrap_infallible();
+        self.delay.delay_ms(10).await;
+    }
+
+    /// Resets the commands and parameters to their S/W Reset default values
+    /// except Deep Sleep Mode.
+    /// RAM is unaffected by this command.
+    async fn software_reset(&mut self) -> Result<(), Error<E>> {
+        self.write_command(command::SW_RESET).await?;
+        // That includes the temperature register, so a written temperature
+        // is gone.
+        self.temperature_written = false;
+        // According to the SSD1681 spec
+        self.delay.delay_ms(10).await;
+        Ok(())
+    }
+
+    async fn set_driver_output(&mut self) -> Result<(), Error<E>> {
+        // The first 9 bits set the number of vertical rows that the display has,
+        // the first 3 bits of the last byte set the gate scanning sequence and direction.
+        // Probably not a good idea to mess with this.
+        self.write_command_data(command::DRIVER_OUTPUT_CONTROL, &[0xc7, 0x00, 0x00])
+            .await?;
+        Ok(())
+    }
 
     // Helpers
 
-    async fn busy_wait(&mut self) {
-        self.busy.wait_for_low().await.unwrap_infallible();
+    #[cfg(not(feature = "blocking"))]
+    async fn busy_wait(&mut self) -> Result<(), Error<E>> {
+        use embassy_futures::select::{select, Either};
+
+        let busy = self.busy.wait_for_low();
+        let timeout = self.delay.delay_ms(self.busy_timeout_ms);
+        match select(busy, timeout).await {
+            Either::First(result) => {
+                result.unwrap_infallible();
+                Ok(())
+            }
+            Either::Second(()) => Err(Error::BusyTimeout),
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    async fn busy_wait(&mut self) -> Result<(), Error<E>> {
+        let mut total_delay_ms = 0;
+        while self.busy.is_high().unwrap_infallible() {
+            if total_delay_ms >= self.busy_timeout_ms {
+                return Err(Error::BusyTimeout);
+            }
+            self.delay.delay_ms(BUSY_POLL_INTERVAL_MS).await;
+            total_delay_ms += BUSY_POLL_INTERVAL_MS;
+        }
+
+        Ok(())
     }
 
     async fn write_command_data(&mut self, command: u8, data: &[u8]) -> Result<(), Error<E>> {
@@ -493,27 +1252,28 @@ where
         self.spi.write(data).await?;
         Ok(())
     }
+
+    async fn read_data(&mut self, buf: &mut [u8]) -> Result<(), Error<E>> {
+        self.dc.set_high().unwrap_infallible();
+        self.spi.read(buf).await?;
+        Ok(())
+    }
 }
 
 // This is how Watchy's Display.cpp does things
 
-impl<SPI, DC, RES, Busy, Delay, E> GDEH0154D67<SPI, DC, RES, Busy, Delay>
+#[maybe_async::maybe_async]
+impl<SPI, DC, RES, Busy, Delay, E> GDEH0154D67<SPI, DC, RES, Busy, Delay, Awake>
 where
     SPI: SpiBus<Error = E>,
     DC: OutputPin<Error = Infallible>,
     RES: OutputPin<Error = Infallible>,
-    Busy: InputPin<Error = Infallible> + Wait,
+    Busy: BusyPin,
     Delay: DelayNs,
 {
-    // pub async fn watchy_hibernate(&mut self) -> Result<(), Error<E>> {
-    //     self.set_deep_sleep_mode(DeepSleepMode::RetainRAM).await
-    // }
-
     // _InitDisplay
-    async fn watchy_init_display(&mut self, is_hybernating: bool) -> Result<(), Error<E>> {
-        if is_hybernating {
-            self.hardware_reset().await;
-        }
+    // Waking up from hibernation already took care of the hardware reset.
+    async fn watchy_init_display(&mut self) -> Result<(), Error<E>> {
         self.software_reset().await?;
 
         self.set_driver_output().await?;
@@ -562,8 +1322,8 @@ where
     }
 
     // _Init_Full and _Init_Part
-    async fn watchy_init(&mut self, is_hybernating: bool) -> Result<(), Error<E>> {
-        self.watchy_init_display(is_hybernating).await?;
+    async fn watchy_init(&mut self) -> Result<(), Error<E>> {
+        self.watchy_init_display().await?;
         self.watchy_power_on().await?;
         Ok(())
     }
@@ -593,13 +1353,13 @@ where
     }
 
     // refresh(true)
-    pub async fn watchy_refresh(&mut self, is_hybernating: bool) -> Result<(), Error<E>> {
-        self.watchy_refresh_partial(0, 0, WIDTH, HEIGHT, is_hybernating)
-            .await
+    pub async fn watchy_refresh(&mut self) -> Result<(), Error<E>> {
+        self.watchy_refresh_partial(0, 0, WIDTH, HEIGHT).await
     }
 
-    pub async fn watchy_refresh_full(&mut self, is_hybernating: bool) -> Result<(), Error<E>> {
-        self.watchy_init(is_hybernating).await?;
+    pub async fn watchy_refresh_full(&mut self) -> Result<(), Error<E>> {
+        self.watchy_init().await?;
+        self.using_partial_mode = false;
         self.set_partial_ram_area(0, 0, WIDTH, HEIGHT).await?;
         self.watchy_update_full().await?;
         Ok(())
@@ -612,7 +1372,6 @@ where
         y: u16,
         width: u16,
         height: u16,
-        is_hybernating: bool,
     ) -> Result<(), Error<E>> {
         // here are a bunch of checks to ensure that the parameters are not out of range
         // of the screen
@@ -624,9 +1383,10 @@ where
         };
         let x = x - (x % 8);
 
-        // if !_using_partial_mode {
-        self.watchy_init(is_hybernating).await?;
-        // }
+        if !self.using_partial_mode {
+            self.watchy_init().await?;
+            self.using_partial_mode = true;
+        }
 
         self.set_partial_ram_area(x, y, width, height).await?;
         self.watchy_update_partial().await?;
@@ -634,3 +1394,6 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,157 @@
+use crate::{GateDrivingVoltage, SourceDrivingVoltage, Vcom};
+
+/// Size of the waveform LUT written with the Write LUT Register command.
+pub const LUT_SIZE: usize = 153;
+
+/// Number of groups (phases A to D plus timing) in a waveform LUT.
+pub const LUT_GROUPS: usize = 12;
+
+/// Number of voltage tables in a waveform LUT, one for each combination
+/// of the BW and RED RAM bits (LUT0 to LUT3) plus one for VCOM (LUT4).
+pub const LUT_VOLTAGE_TABLES: usize = 5;
+
+const VS_SIZE: usize = LUT_VOLTAGE_TABLES * LUT_GROUPS;
+const TIMING_SIZE: usize = 7;
+const FR_OFFSET: usize = VS_SIZE + TIMING_SIZE * LUT_GROUPS;
+const XON_OFFSET: usize = FR_OFFSET + LUT_GROUPS / 2;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Voltage applied to the source (or VCOM for LUT4) during a phase.
+pub enum LutVoltage {
+    #[default]
+    Vss = 0b00,
+    Vsh1 = 0b01,
+    Vsl = 0b10,
+    Vsh2 = 0b11,
+}
+
+impl LutVoltage {
+    fn from_bits(bits: u8) -> Self {
+        match bits & 0b11 {
+            0b00 => LutVoltage::Vss,
+            0b01 => LutVoltage::Vsh1,
+            0b10 => LutVoltage::Vsl,
+            _ => LutVoltage::Vsh2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LutGroup {
+    /// Voltages for phases A, B, C and D of each of the voltage tables.
+    pub voltages: [[LutVoltage; 4]; LUT_VOLTAGE_TABLES],
+
+    /// Length in frames of phases A, B, C and D.
+    pub phase_lengths: [u8; 4],
+
+    /// How many times to repeat phases A and B, and phases C and D.
+    pub state_repeats: [u8; 2],
+
+    /// How many times to repeat the whole group.
+    pub group_repeat: u8,
+
+    /// Frame rate selection, only the lower 4 bits are used.
+    pub frame_rate: u8,
+
+    /// Keep all the gates on during phases A and B.
+    pub gate_on_ab: bool,
+
+    /// Keep all the gates on during phases C and D.
+    pub gate_on_cd: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Waveform used by the controller to drive the panel during a display update.
+pub struct WaveformLut {
+    pub groups: [LutGroup; LUT_GROUPS],
+}
+
+impl WaveformLut {
+    pub fn from_bytes(bytes: &[u8; LUT_SIZE]) -> Self {
+        let mut groups = [LutGroup::default(); LUT_GROUPS];
+
+        for (n, group) in groups.iter_mut().enumerate() {
+            for (table, voltages) in group.voltages.iter_mut().enumerate() {
+                let byte = bytes[table * LUT_GROUPS + n];
+                for (phase, voltage) in voltages.iter_mut().enumerate() {
+                    *voltage = LutVoltage::from_bits(byte >> (6 - phase * 2));
+                }
+            }
+
+            let timing = &bytes[VS_SIZE + n * TIMING_SIZE..][..TIMING_SIZE];
+            group.phase_lengths = [timing[0], timing[1], timing[3], timing[4]];
+            group.state_repeats = [timing[2], timing[5]];
+            group.group_repeat = timing[6];
+
+            group.frame_rate = (bytes[FR_OFFSET + n / 2] >> (4 - (n % 2) * 4)) & 0x0f;
+
+            let xon = bytes[XON_OFFSET + n / 4];
+            group.gate_on_ab = xon & (1 << (7 - (n % 4) * 2)) != 0;
+            group.gate_on_cd = xon & (1 << (6 - (n % 4) * 2)) != 0;
+        }
+
+        WaveformLut { groups }
+    }
+
+    pub fn to_bytes(&self) -> [u8; LUT_SIZE] {
+        let mut bytes = [0; LUT_SIZE];
+
+        for (n, group) in self.groups.iter().enumerate() {
+            for (table, voltages) in group.voltages.iter().enumerate() {
+                bytes[table * LUT_GROUPS + n] = voltages
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (phase, &v)| byte | (v as u8) << (6 - phase * 2));
+            }
+
+            let [tp_a, tp_b, tp_c, tp_d] = group.phase_lengths;
+            let [sr_ab, sr_cd] = group.state_repeats;
+            let timing = [tp_a, tp_b, sr_ab, tp_c, tp_d, sr_cd, group.group_repeat];
+            bytes[VS_SIZE + n * TIMING_SIZE..][..TIMING_SIZE].copy_from_slice(&timing);
+
+            bytes[FR_OFFSET + n / 2] |= (group.frame_rate & 0x0f) << (4 - (n % 2) * 4);
+
+            if group.gate_on_ab {
+                bytes[XON_OFFSET + n / 4] |= 1 << (7 - (n % 4) * 2);
+            }
+            if group.gate_on_cd {
+                bytes[XON_OFFSET + n / 4] |= 1 << (6 - (n % 4) * 2);
+            }
+        }
+
+        bytes
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Register values that usually ship together with a waveform LUT.
+pub struct LutVoltages {
+    /// Option for LUT end (0x3f)
+    pub end_option: u8,
+
+    /// Gate driving voltage (0x03)
+    pub gate: GateDrivingVoltage,
+
+    /// VSH1, VSH2 and VSL source driving voltages (0x04)
+    pub source: SourceDrivingVoltage,
+
+    /// VCOM register value (0x2c)
+    pub vcom: Vcom,
+}
+
+impl LutVoltages {
+    /// The values in the order they're appended to the LUT in vendor
+    /// example code.
+    pub fn to_bytes(self) -> [u8; 6] {
+        let [vsh1, vsh2, vsl] = self.source.to_bytes();
+        [
+            self.end_option,
+            self.gate as u8,
+            vsh1,
+            vsh2,
+            vsl,
+            self.vcom as u8,
+        ]
+    }
+}
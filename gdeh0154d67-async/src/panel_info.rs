@@ -0,0 +1,81 @@
+use bitflags::bitflags;
+
+bitflags! {
+    /// Result of the Status Bit Read command (0x2f).
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct PanelStatus : u8 {
+        /// The high voltages weren't ready during the last HV ready detection.
+        const HV_NOT_READY = 1 << 5;
+        /// VCI was below the level of the last VCI detection.
+        const VCI_LOW = 1 << 4;
+        const BUSY = 1 << 2;
+        /// Chip ID, 0b01 on the SSD1681.
+        const CHIP_ID = 0b11;
+    }
+}
+
+impl PanelStatus {
+    pub fn chip_id(self) -> u8 {
+        (self & Self::CHIP_ID).bits()
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Supply voltage to compare VCI against with VCI Detection (0x15).
+pub enum VciLevel {
+    _2_2V = 0b011,
+    _2_3V = 0b100,
+    _2_4V = 0b101,
+    _2_5V = 0b110,
+    _2_6V = 0b111,
+}
+
+/// Size of the data returned by OTP Registers Read for Display Option (0x2d).
+pub(crate) const DISPLAY_OPTION_SIZE: usize = 11;
+
+/// Size of the data returned by User ID Read (0x2e).
+pub(crate) const USER_ID_SIZE: usize = 10;
+
+/// What the controller knows about the panel it's fitted to, mostly
+/// programmed into its OTP by the panel manufacturer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PanelInfo {
+    /// Whether VCOM comes from OTP or from the VCOM register.
+    pub vcom_otp_selection: u8,
+
+    /// VCOM value programmed in OTP.
+    pub vcom: u8,
+
+    /// Which of the display modes each waveform group uses.
+    pub display_mode: [u8; 5],
+
+    /// Version of the waveform in OTP, the best hint at the panel revision.
+    pub waveform_version: [u8; 4],
+
+    pub user_id: [u8; USER_ID_SIZE],
+
+    pub status: PanelStatus,
+}
+
+impl PanelInfo {
+    pub(crate) fn from_bytes(
+        display_option: &[u8; DISPLAY_OPTION_SIZE],
+        user_id: [u8; USER_ID_SIZE],
+        status: PanelStatus,
+    ) -> Self {
+        let mut display_mode = [0; 5];
+        display_mode.copy_from_slice(&display_option[2..7]);
+        let mut waveform_version = [0; 4];
+        waveform_version.copy_from_slice(&display_option[7..]);
+
+        PanelInfo {
+            vcom_otp_selection: display_option[0],
+            vcom: display_option[1],
+            display_mode,
+            waveform_version,
+            user_id,
+            status,
+        }
+    }
+}
@@ -0,0 +1,923 @@
+extern crate std;
+
+use core::{cell::RefCell, convert::Infallible};
+use std::{collections::VecDeque, rc::Rc, vec, vec::Vec};
+
+use embedded_hal::digital::InputPin;
+
+use crate::{
+    gray::{gray2_plane_byte, Gray2Plane},
+    Awake, BusyPin, DataEntryMode, DisplayUpdateSequence, DummyLinePeriod, Error,
+    GateDrivingVoltage, GateLineWidth, LutGroup, LutVoltage, LutVoltages, PanelInfo, PanelStatus,
+    Pattern, PatternStep, RamLost, RamOptions, RamPlane, SourceDrivingVoltage, SourceVoltageHigh,
+    SourceVoltageLow, TemperatureSensor, VciLevel, Vcom, WaveformLut, GDEH0154D67, LUT_SIZE,
+};
+
+/// Run a driver call to completion, whether the driver is async or blocking.
+#[cfg(not(feature = "blocking"))]
+macro_rules! run {
+    ($call:expr) => {
+        embassy_futures::block_on($call)
+    };
+}
+
+#[cfg(feature = "blocking")]
+macro_rules! run {
+    ($call:expr) => {
+        $call
+    };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Op {
+    Command(u8),
+    Data(Vec<u8>),
+    Read(usize),
+}
+
+#[derive(Default)]
+struct Log {
+    dc_high: bool,
+    ops: Vec<Op>,
+    /// Bytes returned by the display when reading.
+    to_read: VecDeque<u8>,
+}
+
+type SharedLog = Rc<RefCell<Log>>;
+
+/// SPI bus that records every byte together with the state of the D/C pin.
+struct RecordingSpi(SharedLog);
+
+impl embedded_hal::spi::ErrorType for RecordingSpi {
+    type Error = Infallible;
+}
+
+impl RecordingSpi {
+    fn record_read(&mut self, words: &mut [u8]) {
+        let mut log = self.0.borrow_mut();
+        for word in words.iter_mut() {
+            *word = log.to_read.pop_front().unwrap();
+        }
+        log.ops.push(Op::Read(words.len()));
+    }
+
+    fn record_write(&mut self, words: &[u8]) {
+        let mut log = self.0.borrow_mut();
+        if log.dc_high {
+            match log.ops.last_mut() {
+                Some(Op::Data(data)) => data.extend_from_slice(words),
+                _ => log.ops.push(Op::Data(words.to_vec())),
+            }
+        } else {
+            log.ops.extend(words.iter().copied().map(Op::Command));
+        }
+    }
+}
+
+impl embedded_hal_async::spi::SpiBus for RecordingSpi {
+    async fn read(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+        self.record_read(words);
+        Ok(())
+    }
+
+    async fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        self.record_write(words);
+        Ok(())
+    }
+
+    async fn transfer(&mut self, _read: &mut [u8], _write: &[u8]) -> Result<(), Infallible> {
+        unimplemented!()
+    }
+
+    async fn transfer_in_place(&mut self, _words: &mut [u8]) -> Result<(), Infallible> {
+        unimplemented!()
+    }
+
+    async fn flush(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+impl embedded_hal::spi::SpiBus for RecordingSpi {
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Infallible> {
+        self.record_read(words);
+        Ok(())
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        self.record_write(words);
+        Ok(())
+    }
+
+    fn transfer(&mut self, _read: &mut [u8], _write: &[u8]) -> Result<(), Infallible> {
+        unimplemented!()
+    }
+
+    fn transfer_in_place(&mut self, _words: &mut [u8]) -> Result<(), Infallible> {
+        unimplemented!()
+    }
+
+    fn flush(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+struct DataCommandPin(SharedLog);
+
+impl embedded_hal::digital::ErrorType for DataCommandPin {
+    type Error = Infallible;
+}
+
+impl embedded_hal::digital::OutputPin for DataCommandPin {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().dc_high = false;
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().dc_high = true;
+        Ok(())
+    }
+}
+
+struct NoopPin;
+
+impl embedded_hal::digital::ErrorType for NoopPin {
+    type Error = Infallible;
+}
+
+impl embedded_hal::digital::OutputPin for NoopPin {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Busy pin of a panel that finishes every operation instantly.
+struct IdlePin;
+
+impl embedded_hal::digital::ErrorType for IdlePin {
+    type Error = Infallible;
+}
+
+impl InputPin for IdlePin {
+    fn is_high(&mut self) -> Result<bool, Infallible> {
+        Ok(false)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Infallible> {
+        Ok(true)
+    }
+}
+
+#[cfg(not(feature = "blocking"))]
+impl embedded_hal_async::digital::Wait for IdlePin {
+    async fn wait_for_high(&mut self) -> Result<(), Infallible> {
+        core::future::pending().await
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> {
+        core::future::pending().await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> {
+        core::future::pending().await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> {
+        core::future::pending().await
+    }
+}
+
+/// Busy pin of a panel that is disconnected or hung.
+struct StuckPin;
+
+impl embedded_hal::digital::ErrorType for StuckPin {
+    type Error = Infallible;
+}
+
+impl InputPin for StuckPin {
+    fn is_high(&mut self) -> Result<bool, Infallible> {
+        Ok(true)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Infallible> {
+        Ok(false)
+    }
+}
+
+#[cfg(not(feature = "blocking"))]
+impl embedded_hal_async::digital::Wait for StuckPin {
+    async fn wait_for_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Infallible> {
+        core::future::pending().await
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> {
+        core::future::pending().await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> {
+        core::future::pending().await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> {
+        core::future::pending().await
+    }
+}
+
+struct NoopDelay;
+
+impl embedded_hal_async::delay::DelayNs for NoopDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
+
+impl embedded_hal::delay::DelayNs for NoopDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+type TestDisplay<Busy, State = Awake> =
+    GDEH0154D67<RecordingSpi, DataCommandPin, NoopPin, Busy, NoopDelay, State>;
+
+fn display_with_busy_pin<Busy: BusyPin>(busy: Busy) -> (TestDisplay<Busy>, SharedLog) {
+    let log = SharedLog::default();
+    let display = GDEH0154D67::new(
+        RecordingSpi(log.clone()),
+        DataCommandPin(log.clone()),
+        NoopPin,
+        busy,
+        NoopDelay,
+    );
+    let display = run!(display.init()).unwrap();
+
+    // Only record what the test itself sends.
+    log.borrow_mut().ops.clear();
+
+    (display, log)
+}
+
+fn display() -> (TestDisplay<IdlePin>, SharedLog) {
+    display_with_busy_pin(IdlePin)
+}
+
+fn ops(log: &SharedLog) -> Vec<Op> {
+    log.borrow().ops.clone()
+}
+
+#[test]
+fn test_init() {
+    let log = SharedLog::default();
+    let display = GDEH0154D67::new(
+        RecordingSpi(log.clone()),
+        DataCommandPin(log.clone()),
+        NoopPin,
+        IdlePin,
+        NoopDelay,
+    );
+    run!(display.init()).unwrap();
+
+    assert_eq!(
+        ops(&log),
+        vec![
+            Op::Command(0x12),
+            Op::Command(0x01),
+            Op::Data(vec![0xc7, 0x00, 0x00]),
+        ]
+    );
+}
+
+#[test]
+fn test_update_display() {
+    let (mut display, log) = display();
+
+    run!(display.update_display(
+        DisplayUpdateSequence::DRIVE_DISPLAY_PANEL,
+        Some(RamOptions::Invert)
+    ))
+    .unwrap();
+    run!(display.update_display(DisplayUpdateSequence::LOAD_WAVEFORM_LUT_FROM_OTP, None)).unwrap();
+
+    assert_eq!(
+        ops(&log),
+        vec![
+            Op::Command(0x21),
+            Op::Data(vec![0x08]),
+            Op::Command(0x22),
+            Op::Data(vec![0xc7]),
+            Op::Command(0x20),
+            Op::Command(0x22),
+            Op::Data(vec![0xb1]),
+            Op::Command(0x20),
+        ]
+    );
+}
+
+#[test]
+fn test_update_partial_writes_previous_and_current_frame() {
+    let (mut display, log) = display();
+
+    let previous = [0xff, 0xff, 0x00, 0x00];
+    let current = [0x0f, 0xf0, 0xaa, 0x55];
+    run!(display.update_partial(8, 4, 16, 2, &previous, &current)).unwrap();
+
+    assert_eq!(
+        ops(&log),
+        vec![
+            Op::Command(0x11),
+            Op::Data(vec![0x03]),
+            Op::Command(0x44),
+            Op::Data(vec![0x01, 0x02]),
+            Op::Command(0x45),
+            Op::Data(vec![0x04, 0x00, 0x05, 0x00]),
+            Op::Command(0x4e),
+            Op::Data(vec![0x01]),
+            Op::Command(0x4f),
+            Op::Data(vec![0x04, 0x00]),
+            Op::Command(0x26),
+            Op::Data(previous.to_vec()),
+            Op::Command(0x4e),
+            Op::Data(vec![0x01]),
+            Op::Command(0x4f),
+            Op::Data(vec![0x04, 0x00]),
+            Op::Command(0x24),
+            Op::Data(current.to_vec()),
+            Op::Command(0x22),
+            Op::Data(vec![0xfc]),
+            Op::Command(0x20),
+        ]
+    );
+}
+
+#[test]
+fn test_update_partial_checks_buffer_sizes() {
+    let (mut display, log) = display();
+
+    let four = [0; 4];
+    let six = [0; 6];
+    assert!(matches!(
+        run!(display.update_partial(8, 4, 16, 2, &four, &six)),
+        Err(Error::BufferSize)
+    ));
+    assert!(matches!(
+        run!(display.update_partial(8, 4, 16, 2, &six, &six)),
+        Err(Error::BufferSize)
+    ));
+    // A window that isn't aligned to bytes covers 3 bytes per row.
+    assert!(matches!(
+        run!(display.update_partial(4, 4, 16, 2, &four, &four)),
+        Err(Error::BufferSize)
+    ));
+    assert_eq!(ops(&log), []);
+
+    run!(display.update_partial(4, 4, 16, 2, &six, &six)).unwrap();
+}
+
+#[test]
+fn test_update_partial_with_y_decrement() {
+    let (mut display, log) = display();
+    display.set_address_direction(DataEntryMode::X_INCREMENT);
+
+    let previous = [0xff, 0xff, 0x00, 0x00];
+    let current = [0x0f, 0xf0, 0xaa, 0x55];
+    run!(display.update_partial(8, 4, 16, 2, &previous, &current)).unwrap();
+
+    assert_eq!(
+        ops(&log)[..10],
+        [
+            Op::Command(0x11),
+            Op::Data(vec![0x01]),
+            Op::Command(0x44),
+            Op::Data(vec![0x01, 0x02]),
+            Op::Command(0x45),
+            Op::Data(vec![0x05, 0x00, 0x04, 0x00]),
+            Op::Command(0x4e),
+            Op::Data(vec![0x01]),
+            Op::Command(0x4f),
+            Op::Data(vec![0x05, 0x00]),
+        ]
+    );
+    assert_eq!(
+        ops(&log)[12..16],
+        [
+            Op::Command(0x4e),
+            Op::Data(vec![0x01]),
+            Op::Command(0x4f),
+            Op::Data(vec![0x05, 0x00]),
+        ]
+    );
+}
+
+#[test]
+fn test_waveform_lut_bytes_round_trip() {
+    let mut bytes = [0; LUT_SIZE];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (i * 37 % 256) as u8;
+    }
+
+    assert_eq!(WaveformLut::from_bytes(&bytes).to_bytes(), bytes);
+}
+
+#[test]
+fn test_waveform_lut_to_bytes() {
+    let mut lut = WaveformLut::default();
+    lut.groups[1] = LutGroup {
+        voltages: [
+            [
+                LutVoltage::Vsl,
+                LutVoltage::Vsh1,
+                LutVoltage::Vss,
+                LutVoltage::Vsh2,
+            ],
+            [LutVoltage::Vss; 4],
+            [LutVoltage::Vss; 4],
+            [LutVoltage::Vss; 4],
+            [LutVoltage::Vsh2; 4],
+        ],
+        phase_lengths: [1, 2, 3, 4],
+        state_repeats: [5, 6],
+        group_repeat: 7,
+        frame_rate: 0x2,
+        gate_on_ab: false,
+        gate_on_cd: true,
+    };
+
+    let bytes = lut.to_bytes();
+    assert_eq!(bytes[1], 0b10_01_00_11);
+    assert_eq!(bytes[4 * 12 + 1], 0xff);
+    assert_eq!(bytes[60 + 7..60 + 14], [1, 2, 5, 3, 4, 6, 7]);
+    assert_eq!(bytes[144], 0x02);
+    assert_eq!(bytes[150], 0b0001_0000);
+    assert_eq!(WaveformLut::from_bytes(&bytes), lut);
+}
+
+#[test]
+fn test_upload_lut() {
+    let (mut display, log) = display();
+
+    let lut = WaveformLut::from_bytes(&[0x11; LUT_SIZE]);
+    let voltages = LutVoltages {
+        end_option: 0x22,
+        gate: GateDrivingVoltage::_20V,
+        source: SourceDrivingVoltage {
+            vsh1: SourceVoltageHigh::_15V,
+            vsh2: SourceVoltageHigh::_5V,
+            vsl: SourceVoltageLow::Neg15V,
+        },
+        vcom: Vcom::Neg1_2V,
+    };
+    assert_eq!(voltages.to_bytes(), [0x22, 0x17, 0x41, 0xa8, 0x32, 0x30]);
+    run!(display.upload_lut(&lut, voltages)).unwrap();
+
+    assert_eq!(
+        ops(&log),
+        vec![
+            Op::Command(0x32),
+            Op::Data(vec![0x11; LUT_SIZE]),
+            Op::Command(0x3f),
+            Op::Data(vec![0x22]),
+            Op::Command(0x03),
+            Op::Data(vec![0x17]),
+            Op::Command(0x04),
+            Op::Data(vec![0x41, 0xa8, 0x32]),
+            Op::Command(0x2c),
+            Op::Data(vec![0x30]),
+        ]
+    );
+}
+
+#[test]
+fn test_gray2_plane_byte() {
+    // White, light gray, dark gray, black, then the same in reverse.
+    let pixels = [0b11_10_01_00, 0b00_01_10_11];
+
+    assert_eq!(gray2_plane_byte(&pixels, Gray2Plane::Red), 0b0011_1100);
+    assert_eq!(gray2_plane_byte(&pixels, Gray2Plane::Bw), 0b0101_1010);
+}
+
+#[test]
+fn test_update_gray2_writes_both_planes() {
+    let (mut display, log) = display();
+
+    let image = [0b11_11_00_00; 200 * 200 / 4];
+    run!(display.update_gray2(&image, &WaveformLut::gray2(), LutVoltages::gray2())).unwrap();
+
+    let ops = ops(&log);
+    let plane_data = |command| {
+        let i = ops
+            .iter()
+            .position(|op| *op == Op::Command(command))
+            .unwrap();
+        ops[i + 1].clone()
+    };
+
+    assert_eq!(plane_data(0x26), Op::Data(vec![0b0011_0011; 200 * 200 / 8]));
+    assert_eq!(plane_data(0x24), Op::Data(vec![0b0011_0011; 200 * 200 / 8]));
+    assert_eq!(
+        ops[ops.len() - 3..],
+        [Op::Command(0x22), Op::Data(vec![0xc7]), Op::Command(0x20)]
+    );
+}
+
+#[test]
+fn test_update_gray2_checks_buffer_size() {
+    let (mut display, log) = display();
+
+    let image = [0; 200 * 200 / 8];
+    let result = run!(display.update_gray2(&image, &WaveformLut::gray2(), LutVoltages::gray2()));
+
+    assert!(matches!(result, Err(Error::BufferSize)));
+    assert_eq!(ops(&log), []);
+}
+
+#[test]
+fn test_busy_timeout() {
+    let (mut display, log) = display_with_busy_pin(StuckPin);
+    display.set_busy_timeout_ms(100);
+
+    let result = run!(display.update_display(DisplayUpdateSequence::WATCHY_UPDATE_FULL, None));
+
+    assert!(matches!(result, Err(Error::BusyTimeout)));
+    assert_eq!(ops(&log).last(), Some(&Op::Command(0x20)));
+}
+
+#[test]
+fn test_hibernate_and_wake() {
+    let (display, log) = display();
+
+    let display = run!(display.hibernate()).unwrap();
+    let display = run!(display.wake()).unwrap();
+    let display = run!(display.hibernate_and_reset_ram()).unwrap();
+    let _: TestDisplay<IdlePin, RamLost> = run!(display.wake()).unwrap();
+
+    let wake = [
+        Op::Command(0x12),
+        Op::Command(0x01),
+        Op::Data(vec![0xc7, 0x00, 0x00]),
+    ];
+    let mut expected = vec![Op::Command(0x10), Op::Data(vec![0x01])];
+    expected.extend(wake.clone());
+    expected.extend([Op::Command(0x10), Op::Data(vec![0x03])]);
+    expected.extend(wake);
+
+    assert_eq!(ops(&log), expected);
+}
+
+#[test]
+fn test_write_frame_after_losing_ram() {
+    let (display, log) = display();
+    let display = run!(display.hibernate_and_reset_ram()).unwrap();
+    let display = run!(display.wake()).unwrap();
+    log.borrow_mut().ops.clear();
+
+    let image = [0x5a; 200 * 200 / 8];
+    let _: TestDisplay<IdlePin> = run!(display.write_frame(&image)).unwrap();
+
+    let ops = ops(&log);
+    let plane_data = |command| {
+        let i = ops
+            .iter()
+            .position(|op| *op == Op::Command(command))
+            .unwrap();
+        ops[i + 1].clone()
+    };
+    assert_eq!(plane_data(0x24), Op::Data(image.to_vec()));
+    assert_eq!(plane_data(0x26), Op::Data(image.to_vec()));
+}
+
+#[test]
+fn test_write_frame_checks_buffer_size() {
+    let (display, _log) = display();
+    let display = run!(display.hibernate_and_reset_ram()).unwrap();
+    let display = run!(display.wake()).unwrap();
+
+    let result = run!(display.write_frame(&[0; 200 * 200 / 4]));
+    assert!(matches!(result, Err(Error::BufferSize)));
+}
+
+#[test]
+fn test_read_ram() {
+    let (mut display, log) = display();
+    display.set_bus_readable(true);
+    log.borrow_mut().to_read.extend([0x00, 0xaa, 0x55, 0xff]);
+
+    let mut buf = [0; 3];
+    run!(display.read_ram(RamPlane::Red, &mut buf)).unwrap();
+
+    assert_eq!(buf, [0xaa, 0x55, 0xff]);
+    assert_eq!(
+        ops(&log)[10..],
+        [
+            Op::Command(0x41),
+            Op::Data(vec![0x01]),
+            Op::Command(0x27),
+            Op::Read(1),
+            Op::Read(3),
+        ]
+    );
+}
+
+#[test]
+fn test_ram_crc() {
+    let (mut display, log) = display();
+    display.set_bus_readable(true);
+    log.borrow_mut().to_read.extend([0x12, 0x34]);
+
+    assert_eq!(run!(display.ram_crc()).unwrap(), 0x1234);
+    assert_eq!(
+        ops(&log),
+        [Op::Command(0x34), Op::Command(0x35), Op::Read(2)]
+    );
+}
+
+#[test]
+fn test_read_without_readable_bus() {
+    let (mut display, log) = display();
+
+    let mut buf = [0; 3];
+    let result = run!(display.read_ram(RamPlane::Bw, &mut buf));
+    assert!(matches!(result, Err(Error::ReadUnsupported)));
+    assert!(matches!(
+        run!(display.ram_crc()),
+        Err(Error::ReadUnsupported)
+    ));
+    assert_eq!(ops(&log), []);
+}
+
+#[test]
+fn test_pattern_to_byte() {
+    // First value, step height, reserved bit and step width
+    assert_eq!(Pattern::WHITE.to_byte(), 0b1101_0101);
+    assert_eq!(Pattern::BLACK.to_byte(), 0b0101_0101);
+
+    let checkerboard = Pattern {
+        first_value: true,
+        step_height: PatternStep::_16,
+        step_width: PatternStep::_64,
+    };
+    assert_eq!(checkerboard.to_byte(), 0b1001_0011);
+}
+
+#[test]
+fn test_clear() {
+    let (mut display, log) = display();
+
+    run!(display.clear()).unwrap();
+
+    let ops = ops(&log);
+    assert_eq!(ops[10..12], [Op::Command(0x47), Op::Data(vec![0xd5])]);
+    assert_eq!(ops[22..], [Op::Command(0x46), Op::Data(vec![0xd5])]);
+}
+
+#[test]
+fn test_write_temperature() {
+    let (mut display, log) = display();
+
+    run!(display.write_temperature(-5)).unwrap();
+    run!(display.update_display(DisplayUpdateSequence::WATCHY_UPDATE_FULL, None)).unwrap();
+
+    assert_eq!(
+        ops(&log)[..4],
+        [
+            Op::Command(0x1a),
+            Op::Data(vec![0xfb, 0x00]),
+            Op::Command(0x22),
+            // 0xf4 without loading the temperature
+            Op::Data(vec![0xd4]),
+        ]
+    );
+}
+
+#[test]
+fn test_select_sensor_after_write_temperature() {
+    let (mut display, log) = display();
+
+    run!(display.write_temperature(300)).unwrap();
+    run!(display.select_temperature_sensor(TemperatureSensor::Internal)).unwrap();
+    run!(display.update_display(DisplayUpdateSequence::WATCHY_UPDATE_FULL, None)).unwrap();
+
+    assert_eq!(
+        ops(&log)[..6],
+        [
+            Op::Command(0x1a),
+            // Clamped to the highest whole degree that fits
+            Op::Data(vec![0x7f, 0x00]),
+            Op::Command(0x18),
+            Op::Data(vec![0x80]),
+            Op::Command(0x22),
+            Op::Data(vec![0xf4]),
+        ]
+    );
+}
+
+#[test]
+fn test_software_reset_forgets_written_temperature() {
+    let (mut display, log) = display();
+
+    run!(display.write_temperature(-5)).unwrap();
+    run!(display.watchy_refresh_full()).unwrap();
+
+    let ops = ops(&log);
+    let update = ops.iter().rposition(|op| *op == Op::Command(0x22)).unwrap();
+    // 0xf4 loads the temperature again, since the reset cleared it.
+    assert_eq!(ops[update + 1], Op::Data(vec![0xf4]));
+}
+
+#[test]
+fn test_measure_temperature() {
+    let (mut display, log) = display();
+    display.set_bus_readable(true);
+    log.borrow_mut().to_read.extend([0xe8, 0x80]);
+
+    assert_eq!(run!(display.measure_temperature()).unwrap(), -24);
+    assert_eq!(
+        ops(&log),
+        [
+            Op::Command(0x18),
+            Op::Data(vec![0x80]),
+            Op::Command(0x22),
+            Op::Data(vec![0xa1]),
+            Op::Command(0x20),
+            Op::Command(0x1b),
+            Op::Read(2),
+        ]
+    );
+}
+
+#[test]
+fn test_voltage_bytes() {
+    assert_eq!(GateDrivingVoltage::_10V as u8, 0x03);
+    assert_eq!(GateDrivingVoltage::_15_5V as u8, 0x0e);
+    assert_eq!(GateDrivingVoltage::_20V as u8, 0x17);
+
+    assert_eq!(SourceVoltageHigh::_2_4V as u8, 0x8e);
+    assert_eq!(SourceVoltageHigh::_8_8V as u8, 0xce);
+    assert_eq!(SourceVoltageHigh::_9V as u8, 0x23);
+    assert_eq!(SourceVoltageHigh::_17V as u8, 0x4b);
+    assert_eq!(SourceVoltageLow::Neg5V as u8, 0x0a);
+    assert_eq!(SourceVoltageLow::Neg17V as u8, 0x3a);
+
+    assert_eq!(Vcom::Neg0_2V as u8, 0x08);
+    assert_eq!(Vcom::Neg1V as u8, 0x28);
+    assert_eq!(Vcom::Neg3V as u8, 0x78);
+}
+
+#[test]
+fn test_source_driving_voltage_to_bytes() {
+    // The values that go with the grayscale waveform
+    let voltage = SourceDrivingVoltage {
+        vsh1: SourceVoltageHigh::_15V,
+        vsh2: SourceVoltageHigh::_5_6V,
+        vsl: SourceVoltageLow::Neg15V,
+    };
+    assert_eq!(voltage.to_bytes(), [0x41, 0xae, 0x32]);
+    assert_eq!(
+        LutVoltages::gray2().to_bytes(),
+        [0x22, 0x17, 0x41, 0xae, 0x32, 0x28]
+    );
+}
+
+#[test]
+fn test_dummy_line_period_to_byte() {
+    assert_eq!(DummyLinePeriod::new(0).unwrap().to_byte(), 0x00);
+    assert_eq!(DummyLinePeriod::new(0x7f).unwrap().to_byte(), 0x7f);
+    assert_eq!(DummyLinePeriod::new(0x80), None);
+}
+
+#[test]
+fn test_gate_line_width_to_byte() {
+    assert_eq!(GateLineWidth::new(0).unwrap().to_byte(), 0x00);
+    assert_eq!(GateLineWidth::new(0x0f).unwrap().to_byte(), 0x0f);
+    assert_eq!(GateLineWidth::new(0x10), None);
+}
+
+#[test]
+fn test_set_controller_config() {
+    let (mut display, log) = display();
+
+    run!(display.set_gate_driving_voltage(GateDrivingVoltage::_20V)).unwrap();
+    run!(display.set_vcom(Vcom::Neg1V)).unwrap();
+    run!(display.set_dummy_line_period(DummyLinePeriod::new(0x30).unwrap())).unwrap();
+    run!(display.set_gate_line_width(GateLineWidth::new(0x0a).unwrap())).unwrap();
+
+    assert_eq!(
+        ops(&log),
+        [
+            Op::Command(0x03),
+            Op::Data(vec![0x17]),
+            Op::Command(0x2c),
+            Op::Data(vec![0x28]),
+            Op::Command(0x3a),
+            Op::Data(vec![0x30]),
+            Op::Command(0x3b),
+            Op::Data(vec![0x0a]),
+        ]
+    );
+}
+
+#[test]
+fn test_read_panel_info() {
+    let (mut display, log) = display();
+    display.set_bus_readable(true);
+    log.borrow_mut().to_read.extend(1..=21);
+    log.borrow_mut().to_read.push_back(0b0010_0001);
+
+    let info = run!(display.read_panel_info()).unwrap();
+    assert_eq!(
+        info,
+        PanelInfo {
+            vcom_otp_selection: 1,
+            vcom: 2,
+            display_mode: [3, 4, 5, 6, 7],
+            waveform_version: [8, 9, 10, 11],
+            user_id: [12, 13, 14, 15, 16, 17, 18, 19, 20, 21],
+            status: PanelStatus::HV_NOT_READY | PanelStatus::from_bits_retain(0b01),
+        }
+    );
+    assert_eq!(info.status.chip_id(), 0b01);
+    assert_eq!(
+        ops(&log),
+        [
+            Op::Command(0x2d),
+            Op::Read(11),
+            Op::Command(0x2e),
+            Op::Read(10),
+            Op::Command(0x2f),
+            Op::Read(1),
+        ]
+    );
+}
+
+#[test]
+fn test_detect_vci() {
+    let (mut display, log) = display();
+    display.set_bus_readable(true);
+    log.borrow_mut().to_read.extend([0b0001_0001, 0b0000_0001]);
+
+    assert!(!run!(display.detect_vci(VciLevel::_2_4V)).unwrap());
+    assert_eq!(
+        ops(&log),
+        [
+            Op::Command(0x22),
+            Op::Data(vec![0x80]),
+            Op::Command(0x20),
+            Op::Command(0x15),
+            Op::Data(vec![0b101]),
+            Op::Command(0x2f),
+            Op::Read(1),
+            Op::Command(0x22),
+            Op::Data(vec![0x01]),
+            Op::Command(0x20),
+        ]
+    );
+
+    assert!(run!(display.detect_vci(VciLevel::_2_4V)).unwrap());
+}
+
+#[test]
+fn test_detect_hv_ready() {
+    let (mut display, log) = display();
+    display.set_bus_readable(true);
+    log.borrow_mut().to_read.extend([0b0000_0001]);
+
+    assert!(run!(display.detect_hv_ready()).unwrap());
+    assert_eq!(
+        ops(&log),
+        [
+            Op::Command(0x22),
+            Op::Data(vec![0xc0]),
+            Op::Command(0x20),
+            Op::Command(0x14),
+            Op::Data(vec![0x00]),
+            Op::Command(0x2f),
+            Op::Read(1),
+            Op::Command(0x22),
+            Op::Data(vec![0x03]),
+            Op::Command(0x20),
+        ]
+    );
+}
+
+#[test]
+fn test_status_without_readable_bus() {
+    let (mut display, log) = display();
+
+    assert!(matches!(
+        run!(display.read_panel_info()),
+        Err(Error::ReadUnsupported)
+    ));
+    assert!(matches!(
+        run!(display.detect_hv_ready()),
+        Err(Error::ReadUnsupported)
+    ));
+    assert_eq!(ops(&log), []);
+}
@@ -0,0 +1,80 @@
+//! Conversions between the raw types and the `chrono` crate.
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use crate::{InvalidDateTime, RawDate, RawDateTime, RawTime};
+
+impl TryFrom<RawTime> for NaiveTime {
+    type Error = InvalidDateTime;
+
+    fn try_from(time: RawTime) -> Result<Self, InvalidDateTime> {
+        NaiveTime::from_hms_opt(time.hour.into(), time.minute.into(), time.second.into())
+            .ok_or(InvalidDateTime)
+    }
+}
+
+impl From<NaiveTime> for RawTime {
+    fn from(time: NaiveTime) -> Self {
+        RawTime {
+            hour: time.hour() as u8,
+            minute: time.minute() as u8,
+            second: time.second() as u8,
+        }
+    }
+}
+
+impl TryFrom<RawDate> for NaiveDate {
+    type Error = InvalidDateTime;
+
+    fn try_from(date: RawDate) -> Result<Self, InvalidDateTime> {
+        let converted =
+            NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())
+                .ok_or(InvalidDateTime)?;
+
+        if converted.weekday().num_days_from_sunday() != date.weekday.into() {
+            return Err(InvalidDateTime);
+        }
+
+        Ok(converted)
+    }
+}
+
+/// Fails for years the clock can't keep.
+impl TryFrom<NaiveDate> for RawDate {
+    type Error = InvalidDateTime;
+
+    fn try_from(date: NaiveDate) -> Result<Self, InvalidDateTime> {
+        if !(1900..=2099).contains(&date.year()) {
+            return Err(InvalidDateTime);
+        }
+
+        Ok(RawDate {
+            year: date.year() as u16,
+            month: date.month() as u8,
+            day: date.day() as u8,
+            weekday: date.weekday().num_days_from_sunday() as u8,
+        })
+    }
+}
+
+impl TryFrom<RawDateTime> for NaiveDateTime {
+    type Error = InvalidDateTime;
+
+    fn try_from(datetime: RawDateTime) -> Result<Self, InvalidDateTime> {
+        Ok(NaiveDateTime::new(
+            datetime.date().try_into()?,
+            datetime.time().try_into()?,
+        ))
+    }
+}
+
+impl TryFrom<NaiveDateTime> for RawDateTime {
+    type Error = InvalidDateTime;
+
+    fn try_from(datetime: NaiveDateTime) -> Result<Self, InvalidDateTime> {
+        Ok(RawDateTime::new(
+            datetime.date().try_into()?,
+            datetime.time().into(),
+        ))
+    }
+}
@@ -1,5 +1,10 @@
 #![no_std]
 
+use core::ops::RangeInclusive;
+
+#[cfg(feature = "blocking")]
+use embedded_hal::i2c::I2c;
+#[cfg(not(feature = "blocking"))]
 use embedded_hal_async::i2c::I2c;
 
 fn dec_to_bcd(n: u8) -> u8 {
@@ -10,24 +15,22 @@ fn bcd_to_dec(n: u8) -> u8 {
     (n / 16 * 10) + (n % 16)
 }
 
-// #[test]
-// fn test_bcd_to_dec() {
-//     assert_eq!(bcd_to_dec(dec_to_bcd(2023)), 2023);
-// }
-
 #[derive(Debug)]
 pub enum Error<E> {
     Bus(E),
-    Time(time::Error),
     InvalidDateTime,
+    /// The clock stopped at some point, usually because the battery ran
+    /// out, so the time can't be trusted. Set the time, or clear the flag
+    /// with [PCF8563::clear_clock_integrity_flag], to read it again.
+    ClockIntegrityLost,
 }
 
 impl<E: core::fmt::Display> core::fmt::Display for Error<E> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
         match self {
             Error::Bus(e) => write!(f, "Bus error: {}", e),
-            Error::Time(e) => write!(f, "Invalid time: {}", e),
             Error::InvalidDateTime => write!(f, "Invalid time"),
+            Error::ClockIntegrityLost => write!(f, "Clock integrity lost"),
         }
     }
 }
@@ -38,61 +41,236 @@ impl<E> core::convert::From<E> for Error<E> {
     }
 }
 
-fn parse_weekday<E>(weekday: u8) -> Result<time::Weekday, Error<E>> {
-    match weekday {
-        0 => Ok(time::Weekday::Sunday),
-        1 => Ok(time::Weekday::Monday),
-        2 => Ok(time::Weekday::Tuesday),
-        3 => Ok(time::Weekday::Wednesday),
-        4 => Ok(time::Weekday::Thursday),
-        5 => Ok(time::Weekday::Friday),
-        6 => Ok(time::Weekday::Saturday),
-        _ => Err(Error::InvalidDateTime),
+/// A [RawDateTime] that isn't a valid date and time, or whose weekday
+/// doesn't match the date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidDateTime;
+
+/// The time as it's stored in the registers, which isn't checked to make
+/// sense.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RawTime {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl RawTime {
+    pub fn is_valid(&self) -> bool {
+        self.hour < 24 && self.minute < 60 && self.second < 60
     }
 }
 
-fn parse_date<E>(buf: &[u8]) -> Result<time::Date, Error<E>> {
-    let day = bcd_to_dec(buf[0] & mask::DAY);
-    let weekday = bcd_to_dec(buf[1] & mask::WEEKDAY);
+/// The date as it's stored in the registers, which isn't checked to make
+/// sense.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RawDate {
+    /// From 1900 to 2099.
+    pub year: u16,
+    /// From 1 for January.
+    pub month: u8,
+    pub day: u8,
+    /// Number of days from Sunday.
+    pub weekday: u8,
+}
 
-    let month_bcd = buf[2];
-    let year_bcd = buf[3];
+impl RawDate {
+    /// Whether the date exists in the years the clock can keep, and falls on
+    /// the weekday.
+    pub fn is_valid(&self) -> bool {
+        (1900..=2099).contains(&self.year)
+            && (1..=12).contains(&self.month)
+            && (1..=days_in_month(self.year, self.month)).contains(&self.day)
+            && self.weekday == weekday(self.year, self.month, self.day)
+    }
+}
 
-    let month = bcd_to_dec(month_bcd & mask::MONTH);
-    let year = bcd_to_dec(year_bcd) as i32
-        + if month_bcd & mask::CENTURY != 0 {
-            1900
-        } else {
-            2000
-        };
+/// A [RawDate] and [RawTime] together, as read in one go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RawDateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub weekday: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
 
-    let month = time::Month::try_from(month).map_err(|e| Error::Time(e.into()))?;
-    let weekday = parse_weekday(weekday)?;
+impl RawDateTime {
+    pub fn new(date: RawDate, time: RawTime) -> Self {
+        RawDateTime {
+            year: date.year,
+            month: date.month,
+            day: date.day,
+            weekday: date.weekday,
+            hour: time.hour,
+            minute: time.minute,
+            second: time.second,
+        }
+    }
 
-    let date =
-        time::Date::from_calendar_date(year, month, day).map_err(|e| Error::Time(e.into()))?;
+    pub fn date(&self) -> RawDate {
+        RawDate {
+            year: self.year,
+            month: self.month,
+            day: self.day,
+            weekday: self.weekday,
+        }
+    }
 
-    if date.weekday() != weekday {
-        return Err(Error::InvalidDateTime);
+    pub fn time(&self) -> RawTime {
+        RawTime {
+            hour: self.hour,
+            minute: self.minute,
+            second: self.second,
+        }
     }
 
-    Ok(date)
+    pub fn is_valid(&self) -> bool {
+        self.date().is_valid() && self.time().is_valid()
+    }
 }
 
-fn parse_time<E>(buf: &[u8]) -> Result<time::Time, Error<E>> {
-    let second = bcd_to_dec(buf[0] & mask::SECOND);
-    let minute = bcd_to_dec(buf[1] & mask::MINUTE);
-    let hour = bcd_to_dec(buf[2] & mask::HOUR);
+/// Only right for the years the clock can keep, from 1900 to 2099.
+fn is_leap_year(year: u16) -> bool {
+    year & 3 == 0 && year != 1900
+}
 
-    time::Time::from_hms(hour, minute, second).map_err(|e| Error::Time(e.into()))
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
 }
 
-#[derive(Debug, Clone, Default)]
+/// Number of days from Sunday, with Sakamoto's method.
+fn weekday(year: u16, month: u8, day: u8) -> u8 {
+    const MONTH_OFFSETS: [u16; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+
+    let year = if month < 3 { year - 1 } else { year };
+    let days =
+        year + year / 4 - year / 100 + year / 400 + MONTH_OFFSETS[month as usize - 1] + day as u16;
+
+    (days % 7) as u8
+}
+
+fn parse_date(buf: &[u8]) -> RawDate {
+    let century = if buf[2] & mask::CENTURY != 0 {
+        1900
+    } else {
+        2000
+    };
+
+    RawDate {
+        year: century + bcd_to_dec(buf[3]) as u16,
+        month: bcd_to_dec(buf[2] & mask::MONTH),
+        day: bcd_to_dec(buf[0] & mask::DAY),
+        weekday: bcd_to_dec(buf[1] & mask::WEEKDAY),
+    }
+}
+
+fn parse_time<E>(buf: &[u8]) -> Result<RawTime, Error<E>> {
+    if buf[0] & mask::VOLTAGE_LOW != 0 {
+        return Err(Error::ClockIntegrityLost);
+    }
+
+    Ok(RawTime {
+        hour: bcd_to_dec(buf[2] & mask::HOUR),
+        minute: bcd_to_dec(buf[1] & mask::MINUTE),
+        second: bcd_to_dec(buf[0] & mask::SECOND),
+    })
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct AlarmConfig {
     pub minute: Option<u8>,
     pub hour: Option<u8>,
     pub day: Option<u8>,
-    pub weekday: Option<time::Weekday>,
+    /// Number of days from Sunday.
+    pub weekday: Option<u8>,
+}
+
+/// One alarm register, which is `None` if it's not part of the alarm.
+fn parse_alarm<E>(value: u8, mask: u8, range: RangeInclusive<u8>) -> Result<Option<u8>, Error<E>> {
+    if value & ALARM_DISABLED != 0 {
+        return Ok(None);
+    }
+
+    let value = bcd_to_dec(value & mask);
+    if !range.contains(&value) {
+        return Err(Error::InvalidDateTime);
+    }
+
+    Ok(Some(value))
+}
+
+fn parse_alarm_config<E>(buf: &[u8]) -> Result<AlarmConfig, Error<E>> {
+    Ok(AlarmConfig {
+        minute: parse_alarm(buf[0], mask::MINUTE, 0..=59)?,
+        hour: parse_alarm(buf[1], mask::HOUR, 0..=23)?,
+        day: parse_alarm(buf[2], mask::DAY, 1..=31)?,
+        weekday: parse_alarm(buf[3], mask::WEEKDAY, 0..=6)?,
+    })
+}
+
+/// The square wave on the CLKOUT pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockOutput {
+    Disabled,
+    Hz32768,
+    Hz1024,
+    Hz32,
+    Hz1,
+}
+
+impl ClockOutput {
+    fn to_register(self) -> u8 {
+        match self {
+            ClockOutput::Disabled => 0x00,
+            ClockOutput::Hz32768 => mask::SQUARE_WAVE_ENABLED,
+            ClockOutput::Hz1024 => mask::SQUARE_WAVE_ENABLED | 0b01,
+            ClockOutput::Hz32 => mask::SQUARE_WAVE_ENABLED | 0b10,
+            ClockOutput::Hz1 => mask::SQUARE_WAVE_ENABLED | 0b11,
+        }
+    }
+
+    fn from_register(value: u8) -> Self {
+        if value & mask::SQUARE_WAVE_ENABLED == 0 {
+            return ClockOutput::Disabled;
+        }
+
+        match value & mask::SQUARE_WAVE_FREQUENCY {
+            0b00 => ClockOutput::Hz32768,
+            0b01 => ClockOutput::Hz1024,
+            0b10 => ClockOutput::Hz32,
+            _ => ClockOutput::Hz1,
+        }
+    }
+}
+
+/// How fast the countdown timer counts down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerFrequency {
+    Hz4096 = 0b00,
+    Hz64 = 0b01,
+    Hz1 = 0b10,
+    /// Once a minute.
+    PerMinute = 0b11,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerConfig {
+    pub frequency: TimerFrequency,
+    /// Number of ticks until the timer fires, after which it starts over.
+    pub count: u8,
+    /// Pull the interrupt pin low when the timer fires.
+    pub interrupt: bool,
+    /// Only pulse the interrupt pin, instead of keeping it low until the
+    /// timer flag is cleared.
+    pub pulse_mode: bool,
 }
 
 #[allow(dead_code)]
@@ -105,13 +283,21 @@ mod register {
     pub const DAY: u8 = 0x05;
     pub const ALARM_MINUTE: u8 = 0x09;
     pub const CLOCK_OUTPUT: u8 = 0x0D;
+    pub const TIMER_CONTROL: u8 = 0x0E;
+    pub const TIMER: u8 = 0x0F;
 }
 
 #[allow(dead_code)]
 mod mask {
     pub const ALARM_FLAG: u8 = 0x08;
     pub const ALARM_INTERRUPT_ENABLED: u8 = 0x02;
+    pub const TIMER_FLAG: u8 = 0x04;
+    pub const TIMER_INTERRUPT_ENABLED: u8 = 0x01;
+    pub const TIMER_INTERRUPT_PULSE: u8 = 0x10;
+    pub const TIMER_ENABLED: u8 = 0x80;
+    pub const TIMER_FREQUENCY: u8 = 0b00000011;
     pub const SQUARE_WAVE_ENABLED: u8 = 0x80;
+    pub const SQUARE_WAVE_FREQUENCY: u8 = 0b00000011;
 
     pub const CENTURY: u8 = 0x80;
     pub const MONTH: u8 = 0b00011111;
@@ -120,6 +306,7 @@ mod mask {
     pub const HOUR: u8 = 0b00111111;
     pub const MINUTE: u8 = 0b01111111;
     pub const SECOND: u8 = 0b01111111;
+    pub const VOLTAGE_LOW: u8 = 0x80;
 }
 
 const ALARM_DISABLED: u8 = 0x80;
@@ -131,6 +318,7 @@ pub struct PCF8563<I2C> {
 
 pub const SLAVE_ADDRESS: u8 = 0x51;
 
+#[maybe_async::maybe_async]
 impl<I2C: I2c<Error = E>, E> PCF8563<I2C> {
     pub fn new(address: u8, i2c: I2C) -> PCF8563<I2C> {
         PCF8563 { address, i2c }
@@ -152,67 +340,142 @@ impl<I2C: I2c<Error = E>, E> PCF8563<I2C> {
             ALARM_DISABLED, // hour alarm value reset to 00
             ALARM_DISABLED, // day alarm value reset to 00
             ALARM_DISABLED, // weekday alarm value reset to 00
-            0x00,           // set SQW
+            0x00,           // clock output disabled
             0x00,           // timer off
         ])
         .await
     }
 
-    pub async fn read_date(&mut self) -> Result<time::Date, Error<E>> {
+    /// The date as it's stored, without checking that it makes sense.
+    pub async fn read_raw_date(&mut self) -> Result<RawDate, Error<E>> {
         let mut buf = [0; 4];
         self.read_registers(register::DAY, &mut buf).await?;
 
-        parse_date(&buf)
+        Ok(parse_date(&buf))
     }
 
-    pub async fn read_time(&mut self) -> Result<time::Time, Error<E>> {
+    /// The time as it's stored, without checking that it makes sense. Fails
+    /// with [Error::ClockIntegrityLost] if the clock stopped at some point.
+    pub async fn read_raw_time(&mut self) -> Result<RawTime, Error<E>> {
         let mut buf = [0; 3];
         self.read_registers(register::SECOND, &mut buf).await?;
 
         parse_time(&buf)
     }
 
-    pub async fn read_datetime(&mut self) -> Result<time::PrimitiveDateTime, Error<E>> {
+    /// The time as it's stored, even if the clock stopped at some point. It
+    /// kept counting since it started again, so it's still good to wake up
+    /// on time until the time is set.
+    pub async fn read_raw_time_unchecked(&mut self) -> Result<RawTime, Error<E>> {
+        let mut buf = [0; 3];
+        self.read_registers(register::SECOND, &mut buf).await?;
+        buf[0] &= !mask::VOLTAGE_LOW;
+
+        parse_time(&buf)
+    }
+
+    /// The date and time as they're stored, without checking that they make
+    /// sense. Fails with [Error::ClockIntegrityLost] if the clock stopped at
+    /// some point.
+    pub async fn read_raw_datetime(&mut self) -> Result<RawDateTime, Error<E>> {
         let mut buf = [0; 7];
         self.read_registers(register::SECOND, &mut buf).await?;
 
         let time = parse_time(&buf[0..3])?;
-        let date = parse_date(&buf[3..7])?;
+        let date = parse_date(&buf[3..7]);
 
-        Ok(time::PrimitiveDateTime::new(date, time))
+        Ok(RawDateTime::new(date, time))
     }
 
-    pub async fn set_time(&mut self, time: time::Time) -> Result<(), Error<E>> {
+    #[cfg(feature = "time")]
+    pub async fn read_date(&mut self) -> Result<time::Date, Error<E>> {
+        let date = self.read_raw_date().await?;
+
+        date.try_into().map_err(|_| Error::InvalidDateTime)
+    }
+
+    #[cfg(feature = "time")]
+    pub async fn read_time(&mut self) -> Result<time::Time, Error<E>> {
+        let time = self.read_raw_time().await?;
+
+        time.try_into().map_err(|_| Error::InvalidDateTime)
+    }
+
+    #[cfg(feature = "time")]
+    pub async fn read_time_unchecked(&mut self) -> Result<time::Time, Error<E>> {
+        let time = self.read_raw_time_unchecked().await?;
+
+        time.try_into().map_err(|_| Error::InvalidDateTime)
+    }
+
+    #[cfg(feature = "time")]
+    pub async fn read_datetime(&mut self) -> Result<time::PrimitiveDateTime, Error<E>> {
+        let datetime = self.read_raw_datetime().await?;
+
+        datetime.try_into().map_err(|_| Error::InvalidDateTime)
+    }
+
+    /// Whether the clock stopped at some point since the integrity flag was
+    /// cleared, so the time can't be trusted.
+    pub async fn is_clock_integrity_lost(&mut self) -> Result<bool, Error<E>> {
+        let second = self.read_register(register::SECOND).await?;
+
+        Ok(second & mask::VOLTAGE_LOW != 0)
+    }
+
+    /// Trust the time again. Setting the time also clears the flag.
+    pub async fn clear_clock_integrity_flag(&mut self) -> Result<(), Error<E>> {
+        // The flag shares the register with the seconds, which could tick
+        // in between, but the time isn't right anyway if it was set.
+        let second = self.read_register(register::SECOND).await?;
+        self.write(&[register::SECOND, second & !mask::VOLTAGE_LOW])
+            .await
+    }
+
+    /// Set the time, which also clears the clock integrity flag.
+    pub async fn set_raw_time(&mut self, time: &RawTime) -> Result<(), Error<E>> {
         self.write(&[
             register::SECOND,
-            dec_to_bcd(time.second()),
-            dec_to_bcd(time.minute()),
-            dec_to_bcd(time.hour()),
+            dec_to_bcd(time.second),
+            dec_to_bcd(time.minute),
+            dec_to_bcd(time.hour),
         ])
         .await
     }
 
-    pub async fn set_date(&mut self, date: time::Date) -> Result<(), Error<E>> {
-        // time::Month is is represented as an u8 with 1-indexed months so we can just
-        // call .into().
-        let month = date.month().into();
-
-        let month_bcd = if date.year() < 2000 {
-            dec_to_bcd(month) | mask::CENTURY
+    /// Set the date. Years before 2000 set the century bit.
+    pub async fn set_raw_date(&mut self, date: &RawDate) -> Result<(), Error<E>> {
+        let month_bcd = if date.year < 2000 {
+            dec_to_bcd(date.month) | mask::CENTURY
         } else {
-            dec_to_bcd(month)
+            dec_to_bcd(date.month)
         };
 
         self.write(&[
             register::DAY,
-            dec_to_bcd(date.day()),
-            dec_to_bcd(date.weekday().number_days_from_sunday()),
+            dec_to_bcd(date.day),
+            dec_to_bcd(date.weekday),
             month_bcd,
-            dec_to_bcd((date.year() % 100) as u8),
+            dec_to_bcd((date.year % 100) as u8),
         ])
         .await
     }
 
+    /// Set the time, which also clears the clock integrity flag.
+    #[cfg(feature = "time")]
+    pub async fn set_time(&mut self, time: time::Time) -> Result<(), Error<E>> {
+        self.set_raw_time(&time.into()).await
+    }
+
+    #[cfg(feature = "time")]
+    pub async fn set_date(&mut self, date: time::Date) -> Result<(), Error<E>> {
+        let date = date.try_into().map_err(|_| Error::InvalidDateTime)?;
+        self.set_raw_date(&date).await
+    }
+
+    /// Enable the alarm interrupt, clearing the alarm flag so an alarm that
+    /// already went off doesn't fire it right away. Check [Self::alarm_flag]
+    /// first to know whether it did.
     pub async fn enable_alarm(&mut self) -> Result<(), Error<E>> {
         let mut control_status_2 = self.read_register(register::CONTROL_STATUS_2).await?;
         control_status_2 &= !mask::ALARM_FLAG;
@@ -242,9 +505,106 @@ impl<I2C: I2c<Error = E>, E> PCF8563<I2C> {
             alarm.minute.map_or(ALARM_DISABLED, dec_to_bcd),
             alarm.hour.map_or(ALARM_DISABLED, dec_to_bcd),
             alarm.day.map_or(ALARM_DISABLED, dec_to_bcd),
-            alarm
-                .weekday
-                .map_or(ALARM_DISABLED, |w| dec_to_bcd(w.number_days_from_sunday())),
+            alarm.weekday.map_or(ALARM_DISABLED, dec_to_bcd),
+        ])
+        .await
+    }
+
+    /// The alarm as set in the registers.
+    pub async fn read_alarm(&mut self) -> Result<AlarmConfig, Error<E>> {
+        let mut buf = [0; 4];
+        self.read_registers(register::ALARM_MINUTE, &mut buf)
+            .await?;
+
+        parse_alarm_config(&buf)
+    }
+
+    /// Whether the alarm went off since the flag was last cleared.
+    pub async fn alarm_flag(&mut self) -> Result<bool, Error<E>> {
+        let control_status_2 = self.read_register(register::CONTROL_STATUS_2).await?;
+
+        Ok(control_status_2 & mask::ALARM_FLAG != 0)
+    }
+
+    pub async fn clear_alarm_flag(&mut self) -> Result<(), Error<E>> {
+        // Writing 1 leaves a flag as it is, so the timer flag isn't cleared.
+        let control_status_2 = self.read_register(register::CONTROL_STATUS_2).await?;
+        self.write(&[
+            register::CONTROL_STATUS_2,
+            control_status_2 & !mask::ALARM_FLAG,
+        ])
+        .await
+    }
+
+    /// Output a square wave on the CLKOUT pin, which is enabled at 32.768kHz
+    /// after a power cycle. Disable it to save power when it isn't used.
+    pub async fn set_clock_output(&mut self, output: ClockOutput) -> Result<(), Error<E>> {
+        self.write(&[register::CLOCK_OUTPUT, output.to_register()])
+            .await
+    }
+
+    pub async fn clock_output(&mut self) -> Result<ClockOutput, Error<E>> {
+        let clock_output = self.read_register(register::CLOCK_OUTPUT).await?;
+
+        Ok(ClockOutput::from_register(clock_output))
+    }
+
+    /// Set up the countdown timer, stopping it if it was running. It starts
+    /// counting with [Self::enable_timer].
+    pub async fn set_timer(&mut self, timer: &TimerConfig) -> Result<(), Error<E>> {
+        self.write(&[register::TIMER_CONTROL, timer.frequency as u8, timer.count])
+            .await?;
+
+        let mut control_status_2 = self.read_register(register::CONTROL_STATUS_2).await?;
+        control_status_2 &= !(mask::TIMER_INTERRUPT_ENABLED | mask::TIMER_INTERRUPT_PULSE);
+        if timer.interrupt {
+            control_status_2 |= mask::TIMER_INTERRUPT_ENABLED;
+        }
+        if timer.pulse_mode {
+            control_status_2 |= mask::TIMER_INTERRUPT_PULSE;
+        }
+
+        self.write(&[register::CONTROL_STATUS_2, control_status_2])
+            .await
+    }
+
+    /// Start the countdown timer, clearing the timer flag.
+    pub async fn enable_timer(&mut self) -> Result<(), Error<E>> {
+        self.clear_timer_flag().await?;
+
+        let timer_control = self.read_register(register::TIMER_CONTROL).await?;
+        self.write(&[register::TIMER_CONTROL, timer_control | mask::TIMER_ENABLED])
+            .await
+    }
+
+    pub async fn disable_timer(&mut self) -> Result<(), Error<E>> {
+        let timer_control = self.read_register(register::TIMER_CONTROL).await?;
+        self.write(&[
+            register::TIMER_CONTROL,
+            timer_control & !mask::TIMER_ENABLED,
+        ])
+        .await
+    }
+
+    pub async fn is_timer_enabled(&mut self) -> Result<bool, Error<E>> {
+        let timer_control = self.read_register(register::TIMER_CONTROL).await?;
+
+        Ok(timer_control & mask::TIMER_ENABLED != 0)
+    }
+
+    /// Whether the countdown timer fired since the flag was last cleared.
+    pub async fn timer_flag(&mut self) -> Result<bool, Error<E>> {
+        let control_status_2 = self.read_register(register::CONTROL_STATUS_2).await?;
+
+        Ok(control_status_2 & mask::TIMER_FLAG != 0)
+    }
+
+    pub async fn clear_timer_flag(&mut self) -> Result<(), Error<E>> {
+        // Writing 1 leaves a flag as it is, so the alarm flag isn't cleared.
+        let control_status_2 = self.read_register(register::CONTROL_STATUS_2).await?;
+        self.write(&[
+            register::CONTROL_STATUS_2,
+            control_status_2 & !mask::TIMER_FLAG,
         ])
         .await
     }
@@ -269,3 +629,11 @@ impl<I2C: I2c<Error = E>, E> PCF8563<I2C> {
         Ok(())
     }
 }
+
+#[cfg(feature = "chrono")]
+mod chrono_conversions;
+#[cfg(feature = "time")]
+mod time_conversions;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,548 @@
+extern crate std;
+
+#[cfg(any(feature = "time", feature = "chrono"))]
+use core::fmt::Debug;
+use std::{vec, vec::Vec};
+
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction};
+#[cfg(feature = "time")]
+use time::{Date, Month, Time};
+
+#[cfg(any(feature = "time", feature = "chrono"))]
+use crate::InvalidDateTime;
+use crate::{
+    bcd_to_dec, dec_to_bcd, AlarmConfig, ClockOutput, Error, RawDate, RawDateTime, RawTime,
+    TimerConfig, TimerFrequency, PCF8563, SLAVE_ADDRESS,
+};
+
+/// Run a driver call to completion, whether the driver is async or blocking.
+#[cfg(not(feature = "blocking"))]
+macro_rules! run {
+    ($call:expr) => {
+        embassy_futures::block_on($call)
+    };
+}
+
+#[cfg(feature = "blocking")]
+macro_rules! run {
+    ($call:expr) => {
+        $call
+    };
+}
+
+#[cfg(feature = "time")]
+fn date(year: i32, month: Month, day: u8) -> Date {
+    Date::from_calendar_date(year, month, day).unwrap()
+}
+
+#[cfg(feature = "time")]
+fn time(hour: u8, minute: u8, second: u8) -> Time {
+    Time::from_hms(hour, minute, second).unwrap()
+}
+
+/// Friday the 15th of March 2024 at 13:37:42, as the driver reads it and
+/// as the conversions to date libraries are checked against.
+fn raw_datetime() -> RawDateTime {
+    RawDateTime {
+        year: 2024,
+        month: 3,
+        day: 15,
+        weekday: 5,
+        hour: 13,
+        minute: 37,
+        second: 42,
+    }
+}
+
+/// Dates with every field in range that still aren't real dates: the 15th
+/// of March 2024 on a Saturday, and the 30th of February.
+fn invalid_raw_dates() -> [RawDate; 2] {
+    let date = raw_datetime().date();
+    [
+        RawDate { weekday: 6, ..date },
+        RawDate {
+            month: 2,
+            day: 30,
+            ..date
+        },
+    ]
+}
+
+/// A time with the hour out of range.
+fn invalid_raw_time() -> RawTime {
+    RawTime {
+        hour: 24,
+        ..raw_datetime().time()
+    }
+}
+
+/// Run the driver against a bus that expects exactly `expectations`.
+fn with_rtc(expectations: &[Transaction], f: impl FnOnce(&mut PCF8563<I2cMock>)) {
+    let mut i2c = I2cMock::new(expectations);
+    let mut rtc = PCF8563::new(SLAVE_ADDRESS, i2c.clone());
+    f(&mut rtc);
+    i2c.done();
+}
+
+#[test]
+fn test_bcd_to_dec() {
+    for n in 0..100 {
+        assert_eq!(bcd_to_dec(dec_to_bcd(n)), n);
+    }
+    assert_eq!(dec_to_bcd(59), 0x59);
+}
+
+#[test]
+fn test_reset() {
+    let expectations = [Transaction::write(
+        SLAVE_ADDRESS,
+        vec![
+            0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x80, 0x80, 0x80, 0x80,
+            0x00, 0x00,
+        ],
+    )];
+    with_rtc(&expectations, |rtc| run!(rtc.reset()).unwrap());
+}
+
+#[test]
+fn test_set_raw_time() {
+    let expectations = [Transaction::write(
+        SLAVE_ADDRESS,
+        vec![0x02, 0x42, 0x37, 0x13],
+    )];
+    with_rtc(&expectations, |rtc| {
+        run!(rtc.set_raw_time(&raw_datetime().time())).unwrap()
+    });
+}
+
+#[test]
+fn test_set_raw_date() {
+    let expectations = [
+        Transaction::write(SLAVE_ADDRESS, vec![0x05, 0x15, 0x05, 0x03, 0x24]),
+        // The century bit is set for the 20th century.
+        Transaction::write(SLAVE_ADDRESS, vec![0x05, 0x31, 0x05, 0x92, 0x99]),
+    ];
+    with_rtc(&expectations, |rtc| {
+        run!(rtc.set_raw_date(&raw_datetime().date())).unwrap();
+        run!(rtc.set_raw_date(&RawDate {
+            year: 1999,
+            month: 12,
+            day: 31,
+            weekday: 5,
+        }))
+        .unwrap();
+    });
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_set_time() {
+    let expectations = [Transaction::write(
+        SLAVE_ADDRESS,
+        vec![0x02, 0x42, 0x37, 0x13],
+    )];
+    with_rtc(&expectations, |rtc| {
+        run!(rtc.set_time(time(13, 37, 42))).unwrap()
+    });
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_set_date() {
+    let expectations = [
+        // Friday the 15th of March 2024.
+        Transaction::write(SLAVE_ADDRESS, vec![0x05, 0x15, 0x05, 0x03, 0x24]),
+        // The century bit is set for the 20th century.
+        Transaction::write(SLAVE_ADDRESS, vec![0x05, 0x31, 0x05, 0x92, 0x99]),
+    ];
+    with_rtc(&expectations, |rtc| {
+        run!(rtc.set_date(date(2024, Month::March, 15))).unwrap();
+        run!(rtc.set_date(date(1999, Month::December, 31))).unwrap();
+    });
+}
+
+#[test]
+fn test_read_raw_datetime() {
+    let expectations = [
+        Transaction::write_read(
+            SLAVE_ADDRESS,
+            vec![0x02],
+            // The unused bits are ignored.
+            vec![0x42, 0xb7, 0x53, 0x15, 0x05, 0x03, 0x24],
+        ),
+        // Nonsense is read as it is.
+        Transaction::write_read(
+            SLAVE_ADDRESS,
+            vec![0x02],
+            vec![0x59, 0x59, 0x29, 0x31, 0x07, 0x82, 0x00],
+        ),
+    ];
+    with_rtc(&expectations, |rtc| {
+        let datetime = run!(rtc.read_raw_datetime()).unwrap();
+        assert_eq!(datetime, raw_datetime());
+        assert!(datetime.is_valid());
+
+        let datetime = run!(rtc.read_raw_datetime()).unwrap();
+        assert_eq!(
+            datetime,
+            RawDateTime {
+                year: 1900,
+                month: 2,
+                day: 31,
+                weekday: 7,
+                hour: 29,
+                minute: 59,
+                second: 59,
+            }
+        );
+        assert!(!datetime.is_valid());
+    });
+}
+
+#[test]
+fn test_raw_date_is_valid() {
+    let date = |year, month, day, weekday| RawDate {
+        year,
+        month,
+        day,
+        weekday,
+    };
+
+    assert!(date(2024, 3, 15, 5).is_valid());
+    assert!(date(2024, 2, 29, 4).is_valid());
+    assert!(date(2000, 2, 29, 2).is_valid());
+    assert!(date(1900, 1, 1, 1).is_valid());
+    assert!(date(2099, 12, 31, 4).is_valid());
+
+    // Saturday the 15th of March 2024, which was a Friday.
+    assert!(!date(2024, 3, 15, 6).is_valid());
+    assert!(!date(2023, 2, 29, 3).is_valid());
+    assert!(!date(1900, 2, 29, 4).is_valid());
+    assert!(!date(2024, 4, 31, 3).is_valid());
+    assert!(!date(2024, 13, 1, 0).is_valid());
+    assert!(!date(2024, 1, 0, 0).is_valid());
+    assert!(!date(2100, 1, 1, 5).is_valid());
+
+    assert!(RawTime {
+        hour: 23,
+        minute: 59,
+        second: 59
+    }
+    .is_valid());
+    assert!(!invalid_raw_time().is_valid());
+    for date in invalid_raw_dates() {
+        assert!(!date.is_valid());
+    }
+}
+
+/// Check the conversions between the raw types and those of a date library,
+/// given [raw_datetime] and a date the clock can't keep in that library.
+#[cfg(any(feature = "time", feature = "chrono"))]
+fn check_conversions<LibDateTime, LibDate, LibTime>(datetime: LibDateTime, too_late: LibDate)
+where
+    LibDateTime: TryFrom<RawDateTime, Error = InvalidDateTime> + PartialEq + Debug + Copy,
+    RawDateTime: TryFrom<LibDateTime, Error = InvalidDateTime>,
+    LibDate: TryFrom<RawDate, Error = InvalidDateTime> + PartialEq + Debug,
+    RawDate: TryFrom<LibDate, Error = InvalidDateTime>,
+    LibTime: TryFrom<RawTime, Error = InvalidDateTime> + PartialEq + Debug,
+{
+    assert_eq!(LibDateTime::try_from(raw_datetime()), Ok(datetime));
+    assert_eq!(RawDateTime::try_from(datetime), Ok(raw_datetime()));
+
+    for date in invalid_raw_dates() {
+        assert_eq!(LibDate::try_from(date), Err(InvalidDateTime));
+    }
+    assert_eq!(LibTime::try_from(invalid_raw_time()), Err(InvalidDateTime));
+    assert_eq!(RawDate::try_from(too_late), Err(InvalidDateTime));
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_time_conversions() {
+    check_conversions::<_, _, Time>(
+        time::PrimitiveDateTime::new(date(2024, Month::March, 15), time(13, 37, 42)),
+        date(2100, Month::January, 1),
+    );
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_chrono_conversions() {
+    use chrono::{NaiveDate, NaiveTime};
+
+    let date = |year, month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    check_conversions::<_, _, NaiveTime>(
+        date(2024, 3, 15).and_hms_opt(13, 37, 42).unwrap(),
+        date(2100, 1, 1),
+    );
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_read_datetime() {
+    let expectations = [Transaction::write_read(
+        SLAVE_ADDRESS,
+        vec![0x02],
+        // The unused bits are ignored.
+        vec![0x42, 0xb7, 0x53, 0x15, 0x05, 0x03, 0x24],
+    )];
+    with_rtc(&expectations, |rtc| {
+        let datetime = run!(rtc.read_datetime()).unwrap();
+        assert_eq!(datetime.date(), date(2024, Month::March, 15));
+        assert_eq!(datetime.time(), time(13, 37, 42));
+    });
+}
+
+#[test]
+fn test_clock_integrity_lost() {
+    let expectations = [
+        // The VL bit is set.
+        Transaction::write_read(
+            SLAVE_ADDRESS,
+            vec![0x02],
+            vec![0xc2, 0x37, 0x13, 0x15, 0x05, 0x03, 0x24],
+        ),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x02], vec![0xc2, 0x37, 0x13]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x02], vec![0xc2, 0x37, 0x13]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x02], vec![0xc2]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x02], vec![0xc2]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x02, 0x42]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x02], vec![0x43]),
+    ];
+    with_rtc(&expectations, |rtc| {
+        assert!(matches!(
+            run!(rtc.read_raw_datetime()),
+            Err(Error::ClockIntegrityLost)
+        ));
+        assert!(matches!(
+            run!(rtc.read_raw_time()),
+            Err(Error::ClockIntegrityLost)
+        ));
+        assert_eq!(
+            run!(rtc.read_raw_time_unchecked()).unwrap(),
+            RawTime {
+                hour: 13,
+                minute: 37,
+                second: 42,
+            }
+        );
+        assert!(run!(rtc.is_clock_integrity_lost()).unwrap());
+        run!(rtc.clear_clock_integrity_flag()).unwrap();
+        assert!(!run!(rtc.is_clock_integrity_lost()).unwrap());
+    });
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_read_date_with_wrong_weekday() {
+    let expectations = [Transaction::write_read(
+        SLAVE_ADDRESS,
+        vec![0x05],
+        // Saturday the 15th of March 2024, which was a Friday.
+        vec![0x15, 0x06, 0x03, 0x24],
+    )];
+    with_rtc(&expectations, |rtc| {
+        assert!(matches!(run!(rtc.read_date()), Err(Error::InvalidDateTime)))
+    });
+}
+
+#[test]
+fn test_set_alarm() {
+    let expectations = [
+        Transaction::write(SLAVE_ADDRESS, vec![0x09, 0x30, 0x07, 0x80, 0x80]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x09, 0x80, 0x80, 0x28, 0x06]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x09, 0x80, 0x80, 0x80, 0x80]),
+    ];
+    with_rtc(&expectations, |rtc| {
+        run!(rtc.set_alarm(&AlarmConfig {
+            minute: Some(30),
+            hour: Some(7),
+            ..Default::default()
+        }))
+        .unwrap();
+        run!(rtc.set_alarm(&AlarmConfig {
+            day: Some(28),
+            weekday: Some(6),
+            ..Default::default()
+        }))
+        .unwrap();
+        run!(rtc.set_alarm(&AlarmConfig::default())).unwrap();
+    });
+}
+
+#[test]
+fn test_read_alarm() {
+    let expectations = [
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x09], vec![0x30, 0x07, 0x80, 0x80]),
+        // The unused bits are ignored.
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x09], vec![0x80, 0xff, 0x68, 0x0e]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x09], vec![0x80, 0x80, 0x80, 0x80]),
+    ];
+    with_rtc(&expectations, |rtc| {
+        assert_eq!(
+            run!(rtc.read_alarm()).unwrap(),
+            AlarmConfig {
+                minute: Some(30),
+                hour: Some(7),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            run!(rtc.read_alarm()).unwrap(),
+            AlarmConfig {
+                day: Some(28),
+                weekday: Some(6),
+                ..Default::default()
+            }
+        );
+        assert_eq!(run!(rtc.read_alarm()).unwrap(), AlarmConfig::default());
+    });
+}
+
+#[test]
+fn test_read_invalid_alarm() {
+    let expectations = [
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x09], vec![0x60, 0x80, 0x80, 0x80]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x09], vec![0x80, 0x80, 0x00, 0x80]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x09], vec![0x80, 0x80, 0x80, 0x07]),
+    ];
+    with_rtc(&expectations, |rtc| {
+        for _ in 0..3 {
+            assert!(matches!(
+                run!(rtc.read_alarm()),
+                Err(Error::InvalidDateTime)
+            ));
+        }
+    });
+}
+
+#[test]
+fn test_alarm_flag() {
+    let expectations = [
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x0e]),
+        // Clearing it leaves the timer flag as it is.
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x0e]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x01, 0x06]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x06]),
+    ];
+    with_rtc(&expectations, |rtc| {
+        assert!(run!(rtc.alarm_flag()).unwrap());
+        run!(rtc.clear_alarm_flag()).unwrap();
+        assert!(!run!(rtc.alarm_flag()).unwrap());
+    });
+}
+
+#[test]
+fn test_enable_disable_alarm() {
+    let expectations = [
+        // Enabling clears a pending alarm flag and keeps the other bits.
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x19]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x01, 0x13]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x13]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x01, 0x11]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x11]),
+    ];
+    with_rtc(&expectations, |rtc| {
+        run!(rtc.enable_alarm()).unwrap();
+        run!(rtc.disable_alarm()).unwrap();
+        assert!(!run!(rtc.is_alarm_enabled()).unwrap());
+    });
+}
+
+#[test]
+fn test_set_timer() {
+    let expectations = [
+        Transaction::write(SLAVE_ADDRESS, vec![0x0e, 0x02, 0x0a]),
+        // Keeps the alarm bits, and the flags as they are by writing them as 1.
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x0e]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x01, 0x1f]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x0e, 0x03, 0x05]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x1f]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x01, 0x0e]),
+    ];
+    with_rtc(&expectations, |rtc| {
+        run!(rtc.set_timer(&TimerConfig {
+            frequency: TimerFrequency::Hz1,
+            count: 10,
+            interrupt: true,
+            pulse_mode: true,
+        }))
+        .unwrap();
+        run!(rtc.set_timer(&TimerConfig {
+            frequency: TimerFrequency::PerMinute,
+            count: 5,
+            interrupt: false,
+            pulse_mode: false,
+        }))
+        .unwrap();
+    });
+}
+
+#[test]
+fn test_enable_disable_timer() {
+    let expectations = [
+        // Enabling clears a pending timer flag but not the alarm flag.
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x0d]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x01, 0x09]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x0e], vec![0x02]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x0e, 0x82]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x0e], vec![0x82]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x0e], vec![0x82]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x0e, 0x02]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x0e], vec![0x02]),
+    ];
+    with_rtc(&expectations, |rtc| {
+        run!(rtc.enable_timer()).unwrap();
+        assert!(run!(rtc.is_timer_enabled()).unwrap());
+        run!(rtc.disable_timer()).unwrap();
+        assert!(!run!(rtc.is_timer_enabled()).unwrap());
+    });
+}
+
+#[test]
+fn test_timer_flag() {
+    let expectations = [
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x05]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x05]),
+        Transaction::write(SLAVE_ADDRESS, vec![0x01, 0x01]),
+        Transaction::write_read(SLAVE_ADDRESS, vec![0x01], vec![0x01]),
+    ];
+    with_rtc(&expectations, |rtc| {
+        assert!(run!(rtc.timer_flag()).unwrap());
+        run!(rtc.clear_timer_flag()).unwrap();
+        assert!(!run!(rtc.timer_flag()).unwrap());
+    });
+}
+
+#[test]
+fn test_clock_output() {
+    let outputs = [
+        (ClockOutput::Disabled, 0x00),
+        (ClockOutput::Hz32768, 0x80),
+        (ClockOutput::Hz1024, 0x81),
+        (ClockOutput::Hz32, 0x82),
+        (ClockOutput::Hz1, 0x83),
+    ];
+    let expectations: Vec<_> = outputs
+        .iter()
+        .flat_map(|&(_, value)| {
+            [
+                Transaction::write(SLAVE_ADDRESS, vec![0x0d, value]),
+                Transaction::write_read(SLAVE_ADDRESS, vec![0x0d], vec![value]),
+            ]
+        })
+        // The frequency doesn't matter while it's disabled.
+        .chain([Transaction::write_read(
+            SLAVE_ADDRESS,
+            vec![0x0d],
+            vec![0x03],
+        )])
+        .collect();
+    with_rtc(&expectations, |rtc| {
+        for (output, _) in outputs {
+            run!(rtc.set_clock_output(output)).unwrap();
+            assert_eq!(run!(rtc.clock_output()).unwrap(), output);
+        }
+        assert_eq!(run!(rtc.clock_output()).unwrap(), ClockOutput::Disabled);
+    });
+}
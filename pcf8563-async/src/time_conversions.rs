@@ -0,0 +1,77 @@
+//! Conversions between the raw types and the `time` crate.
+
+use crate::{InvalidDateTime, RawDate, RawDateTime, RawTime};
+
+impl TryFrom<RawTime> for time::Time {
+    type Error = InvalidDateTime;
+
+    fn try_from(time: RawTime) -> Result<Self, InvalidDateTime> {
+        time::Time::from_hms(time.hour, time.minute, time.second).map_err(|_| InvalidDateTime)
+    }
+}
+
+impl From<time::Time> for RawTime {
+    fn from(time: time::Time) -> Self {
+        RawTime {
+            hour: time.hour(),
+            minute: time.minute(),
+            second: time.second(),
+        }
+    }
+}
+
+impl TryFrom<RawDate> for time::Date {
+    type Error = InvalidDateTime;
+
+    fn try_from(date: RawDate) -> Result<Self, InvalidDateTime> {
+        let month = time::Month::try_from(date.month).map_err(|_| InvalidDateTime)?;
+        let converted = time::Date::from_calendar_date(date.year.into(), month, date.day)
+            .map_err(|_| InvalidDateTime)?;
+
+        if converted.weekday().number_days_from_sunday() != date.weekday {
+            return Err(InvalidDateTime);
+        }
+
+        Ok(converted)
+    }
+}
+
+/// Fails for years the clock can't keep.
+impl TryFrom<time::Date> for RawDate {
+    type Error = InvalidDateTime;
+
+    fn try_from(date: time::Date) -> Result<Self, InvalidDateTime> {
+        if !(1900..=2099).contains(&date.year()) {
+            return Err(InvalidDateTime);
+        }
+
+        Ok(RawDate {
+            year: date.year() as u16,
+            month: date.month().into(),
+            day: date.day(),
+            weekday: date.weekday().number_days_from_sunday(),
+        })
+    }
+}
+
+impl TryFrom<RawDateTime> for time::PrimitiveDateTime {
+    type Error = InvalidDateTime;
+
+    fn try_from(datetime: RawDateTime) -> Result<Self, InvalidDateTime> {
+        Ok(time::PrimitiveDateTime::new(
+            datetime.date().try_into()?,
+            datetime.time().try_into()?,
+        ))
+    }
+}
+
+impl TryFrom<time::PrimitiveDateTime> for RawDateTime {
+    type Error = InvalidDateTime;
+
+    fn try_from(datetime: time::PrimitiveDateTime) -> Result<Self, InvalidDateTime> {
+        Ok(RawDateTime::new(
+            datetime.date().try_into()?,
+            datetime.time().into(),
+        ))
+    }
+}
@@ -0,0 +1,92 @@
+//! Renders the watch faces on the host, so they can be worked on without
+//! flashing the Watchy.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use watchy_core::{
+    draw_buffer::{DrawBuffer, Rotation, WIDTH},
+    face::{FaceData, SimpleFace, WatchFace},
+    hal::Buttons,
+};
+
+/// Screens drawn by [SimpleFace].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    Time,
+    ButtonPressed,
+}
+
+/// Draw a screen of [SimpleFace] the same way the firmware does.
+pub fn render(screen: Screen, data: &FaceData, rotation: Rotation) -> DrawBuffer {
+    let mut face = SimpleFace::default();
+    if screen == Screen::ButtonPressed {
+        face.on_button(Buttons::TOP_LEFT);
+    }
+
+    render_face(&mut face, data, rotation)
+}
+
+/// Draw any face the same way the firmware does.
+pub fn render_face<F: WatchFace>(face: &mut F, data: &FaceData, rotation: Rotation) -> DrawBuffer {
+    let mut buffer = DrawBuffer::empty();
+    buffer.set_rotation(rotation);
+    face.render(data, &mut buffer);
+
+    buffer
+}
+
+/// Write the image as the display shows it as a binary PBM.
+pub fn write_pbm<W: Write>(buffer: &DrawBuffer, mut out: W) -> io::Result<()> {
+    write!(out, "P4\n{} {}\n", WIDTH, WIDTH)?;
+    for y in 0..WIDTH {
+        // PBM uses 1 for black, the display 1 for white.
+        let row: Vec<u8> = buffer.row(y).iter().map(|byte| !byte).collect();
+        out.write_all(&row)?;
+    }
+
+    Ok(())
+}
+
+/// Write the image as the display shows it as a 1-bit grayscale PNG.
+pub fn write_png<W: Write>(buffer: &DrawBuffer, out: W) -> Result<(), png::EncodingError> {
+    let mut encoder = png::Encoder::new(out, WIDTH as u32, WIDTH as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::One);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&rows(buffer))?;
+    writer.finish()
+}
+
+/// Read a PNG written by [write_png], as the same rows as [DrawBuffer::row].
+pub fn read_png(path: &Path) -> Result<Vec<u8>, png::DecodingError> {
+    let decoder = png::Decoder::new(File::open(path)?);
+    let mut reader = decoder.read_info()?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data)?;
+    data.truncate(info.buffer_size());
+
+    Ok(data)
+}
+
+/// All the rows of the image as the display shows it.
+pub fn rows(buffer: &DrawBuffer) -> Vec<u8> {
+    (0..WIDTH).flat_map(|y| buffer.row(y)).copied().collect()
+}
+
+/// Save the image as a PBM or a PNG, depending on the extension of `path`.
+pub fn save(buffer: &DrawBuffer, path: &Path) -> io::Result<()> {
+    let out = BufWriter::new(File::create(path)?);
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("pbm") => write_pbm(buffer, out),
+        Some("png") => write_png(buffer, out).map_err(io::Error::other),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the output must be a .pbm or .png file",
+        )),
+    }
+}
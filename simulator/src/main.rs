@@ -0,0 +1,138 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use time::{Duration, Time};
+use watchy_core::{draw_buffer::Rotation, face::FaceData};
+use watchy_simulator::{render, save, Screen};
+
+const USAGE: &str = "\
+Usage: watchy-simulator [OPTIONS] OUTPUT
+
+Draw a screen of the firmware into OUTPUT, a .pbm or .png file.
+
+Options:
+  --screen time|button      Screen to draw [default: time]
+  --time HH:MM              Time to show [default: 10:09]
+  --battery PERCENT         Battery charge to show [default: 100]
+  --steps STEPS             Step count to show [default: 0]
+  --rotation 0|90|180|270   Clockwise rotation of the image [default: 0]
+  --frames N                Draw N frames a minute apart, into OUTPUT with the
+                            frame number added before the extension [default: 1]";
+
+struct Options {
+    screen: Screen,
+    data: FaceData,
+    rotation: Rotation,
+    frames: u32,
+    output: PathBuf,
+}
+
+fn parse_time(s: &str) -> Option<Time> {
+    let (hour, minute) = s.split_once(':')?;
+    Time::from_hms(hour.parse().ok()?, minute.parse().ok()?, 0).ok()
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        screen: Screen::Time,
+        data: FaceData {
+            time: Time::from_hms(10, 9, 0).unwrap(),
+            battery_percentage: 100,
+            steps: 0,
+        },
+        rotation: Rotation::Rotate0,
+        frames: 1,
+        output: PathBuf::new(),
+    };
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            output = Some(PathBuf::from(arg));
+            continue;
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        let invalid = || format!("invalid value for {}: {}", arg, value);
+
+        match arg.as_str() {
+            "--screen" => {
+                options.screen = match value.as_str() {
+                    "time" => Screen::Time,
+                    "button" => Screen::ButtonPressed,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--time" => options.data.time = parse_time(&value).ok_or_else(invalid)?,
+            "--battery" => {
+                options.data.battery_percentage = value
+                    .parse()
+                    .ok()
+                    .filter(|&p| p <= 100)
+                    .ok_or_else(invalid)?
+            }
+            "--steps" => options.data.steps = value.parse().map_err(|_| invalid())?,
+            "--rotation" => {
+                options.rotation = match value.as_str() {
+                    "0" => Rotation::Rotate0,
+                    "90" => Rotation::Rotate90,
+                    "180" => Rotation::Rotate180,
+                    "270" => Rotation::Rotate270,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--frames" => {
+                options.frames = value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?
+            }
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    options.output = output.ok_or("missing OUTPUT")?;
+    Ok(options)
+}
+
+/// `face.png` becomes `face-3.png` for the frame 3.
+fn frame_path(output: &Path, frame: u32) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{}-{}", stem, frame);
+    if let Some(extension) = output.extension() {
+        name = format!("{}.{}", name, extension.to_string_lossy());
+    }
+
+    output.with_file_name(name)
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut data = options.data;
+    for frame in 0..options.frames {
+        let path = if options.frames == 1 {
+            options.output.clone()
+        } else {
+            frame_path(&options.output, frame)
+        };
+
+        let buffer = render(options.screen, &data, options.rotation);
+        if let Err(error) = save(&buffer, &path) {
+            eprintln!("failed to write {}: {}", path.display(), error);
+            return ExitCode::FAILURE;
+        }
+
+        data.time += Duration::minutes(1);
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,73 @@
+//! Compare the screens with the images in `tests/golden`.
+//!
+//! After an intended change, regenerate the images with
+//! `UPDATE_GOLDEN=1 cargo test-host --manifest-path simulator/Cargo.toml`
+//! and review them with the rest of the change.
+
+use std::{env, path::PathBuf};
+
+use time::Time;
+use watchy_core::{draw_buffer::Rotation, face::FaceData};
+use watchy_simulator::{read_png, render, rows, save, Screen};
+
+fn data() -> FaceData {
+    FaceData {
+        time: Time::from_hms(10, 9, 0).unwrap(),
+        battery_percentage: 85,
+        steps: 1234,
+    }
+}
+
+fn check(name: &str, screen: Screen, data: &FaceData, rotation: Rotation) {
+    let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name));
+    let buffer = render(screen, data, rotation);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        save(&buffer, &golden).unwrap();
+        return;
+    }
+
+    let expected =
+        read_png(&golden).unwrap_or_else(|e| panic!("failed to read {}: {}", golden.display(), e));
+    if rows(&buffer) != expected {
+        let actual = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.png", name));
+        save(&buffer, &actual).unwrap();
+        panic!(
+            "{} doesn't match {}, set UPDATE_GOLDEN if that's intended",
+            actual.display(),
+            golden.display()
+        );
+    }
+}
+
+#[test]
+fn test_time() {
+    check("time", Screen::Time, &data(), Rotation::Rotate0);
+}
+
+#[test]
+fn test_time_rotated() {
+    check("time_rotate90", Screen::Time, &data(), Rotation::Rotate90);
+}
+
+#[test]
+fn test_time_long_values() {
+    let data = FaceData {
+        time: Time::from_hms(23, 59, 0).unwrap(),
+        battery_percentage: 100,
+        steps: u32::MAX,
+    };
+    check("time_long_values", Screen::Time, &data, Rotation::Rotate0);
+}
+
+#[test]
+fn test_button_pressed() {
+    check(
+        "button_pressed",
+        Screen::ButtonPressed,
+        &data(),
+        Rotation::Rotate0,
+    );
+}
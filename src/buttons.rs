@@ -1,5 +1,10 @@
 use bitflags::bitflags;
-use esp_hal::peripherals::LPWR;
+use esp_hal::{
+    gpio::{AnyPin, Flex, GpioPin, Pull},
+    peripheral::Peripheral,
+    peripherals::LPWR,
+};
+use watchy_core::hal::Buttons;
 
 bitflags! {
     pub struct WakeupButtons : u32 {
@@ -17,3 +22,68 @@ impl WakeupButtons {
         WakeupButtons::from_bits_retain(wakeup_bits)
     }
 }
+
+impl From<WakeupButtons> for Buttons {
+    fn from(wakeup: WakeupButtons) -> Self {
+        let mut buttons = Buttons::empty();
+        buttons.set(
+            Buttons::TOP_RIGHT,
+            wakeup.contains(WakeupButtons::TOP_RIGHT),
+        );
+        buttons.set(Buttons::TOP_LEFT, wakeup.contains(WakeupButtons::TOP_LEFT));
+        buttons.set(
+            Buttons::BOTTOM_LEFT,
+            wakeup.contains(WakeupButtons::BOTTOM_LEFT),
+        );
+        buttons.set(
+            Buttons::BOTTOM_RIGHT,
+            wakeup.contains(WakeupButtons::BOTTOM_RIGHT),
+        );
+
+        buttons
+    }
+}
+
+/// The pins of the buttons, which are pulled high while they're pressed.
+///
+/// They're set up as inputs once, and can still be handed to the EXT1
+/// wakeup source before going to sleep.
+pub struct ButtonPins {
+    pub top_left: Flex<'static>,
+    pub top_right: Flex<'static>,
+    pub bottom_left: Flex<'static>,
+    pub bottom_right: Flex<'static>,
+}
+
+impl ButtonPins {
+    pub fn new(
+        top_left: GpioPin<25>,
+        top_right: GpioPin<35>,
+        bottom_left: GpioPin<26>,
+        bottom_right: GpioPin<4>,
+    ) -> Self {
+        fn input(pin: impl Peripheral<P = impl Into<AnyPin>> + 'static) -> Flex<'static> {
+            let mut pin = Flex::new(pin);
+            pin.set_as_input(Pull::None);
+            pin
+        }
+
+        ButtonPins {
+            top_left: input(top_left),
+            top_right: input(top_right),
+            bottom_left: input(bottom_left),
+            bottom_right: input(bottom_right),
+        }
+    }
+
+    /// The buttons held down right now.
+    pub fn pressed(&self) -> Buttons {
+        let mut buttons = Buttons::empty();
+        buttons.set(Buttons::TOP_LEFT, self.top_left.is_high());
+        buttons.set(Buttons::TOP_RIGHT, self.top_right.is_high());
+        buttons.set(Buttons::BOTTOM_LEFT, self.bottom_left.is_high());
+        buttons.set(Buttons::BOTTOM_RIGHT, self.bottom_right.is_high());
+
+        buttons
+    }
+}
@@ -1,18 +1,19 @@
 use core::fmt::Debug;
 
 use defmt::Format;
-use embassy_embedded_hal::shared_bus::asynch::i2c::I2cDevice;
+use embassy_embedded_hal::shared_bus::{asynch::i2c::I2cDevice, I2cDeviceError};
 use embassy_sync::{blocking_mutex::raw::NoopRawMutex, mutex::Mutex};
 use esp_hal::{
     self,
     dma::{DmaRxBuf, DmaTxBuf},
     dma_buffers,
-    gpio::{GpioPin, Input, Output},
+    gpio::{GpioPin, Input, Output, RtcPin},
     i2c::{self, master::I2c},
     peripherals::LPWR,
+    ram,
     reset::SleepSource,
     rtc_cntl::{
-        sleep::{Ext0WakeupSource, Ext1WakeupSource},
+        sleep::{Ext0WakeupSource, Ext1WakeupSource, RtcSleepConfig, WakeupLevel},
         Rtc,
     },
     spi::{
@@ -24,9 +25,21 @@ use esp_hal::{
     Async,
 };
 use static_cell::StaticCell;
+use watchy_core::{
+    app,
+    draw_buffer::{DrawBuffer, Gray2DrawBuffer},
+    face::WatchFace,
+    hal::{
+        self, Accelerometer, ButtonInput, Buttons, Clock, DisplaySink, Haptics, Refresh,
+        WakeupCause,
+    },
+    persist::Record,
+    refresh_policy::{RefreshPolicy, RefreshState},
+};
 
 use crate::{
-    battery::Battery, buttons::WakeupButtons, draw_buffer::DrawBuffer,
+    battery::Battery,
+    buttons::{ButtonPins, WakeupButtons},
     vibration_motor::VibrationMotor,
 };
 
@@ -35,6 +48,14 @@ pub enum Error {
     I2cConfig(i2c::master::ConfigError),
     SpiConfig(spi::master::ConfigError),
     Spi(spi::Error),
+    /// The display didn't finish an operation in time, it might be disconnected.
+    DisplayBusyTimeout,
+    /// A previous display operation failed and the driver was lost with it.
+    DisplayUnavailable,
+    /// The display's SDA line isn't connected to MISO, so it can't be read from.
+    DisplayReadUnsupported,
+    /// An image sent to the display didn't match the area it was drawn to.
+    DisplayBufferSize,
     Interrupt(esp_hal::interrupt::Error),
 }
 
@@ -60,56 +81,56 @@ impl From<gdeh0154d67_async::Error<spi::Error>> for Error {
     fn from(value: gdeh0154d67_async::Error<spi::Error>) -> Self {
         match value {
             gdeh0154d67_async::Error::Spi(spi) => Error::Spi(spi),
+            gdeh0154d67_async::Error::BusyTimeout => Error::DisplayBusyTimeout,
+            gdeh0154d67_async::Error::ReadUnsupported => Error::DisplayReadUnsupported,
+            gdeh0154d67_async::Error::BufferSize => Error::DisplayBufferSize,
         }
     }
 }
 
 pub type I2cBusDevice<'a> = I2cDevice<'a, NoopRawMutex, I2c<'static, Async>>;
 
-type Display<'a> = gdeh0154d67_async::GDEH0154D67<
+type Display<'a, State> = gdeh0154d67_async::GDEH0154D67<
     SpiDmaBus<'a, esp_hal::Async>,
     Output<'a>,
     Output<'a>,
     Input<'a>,
     embassy_time::Delay,
+    State,
 >;
 
-/// GPIO pins used to wake up the device during sleep
-pub struct WakeupPins {
-    external_rtc: GpioPin<27>,
-    btn_bottom_left: GpioPin<26>,
-    btn_bottom_right: GpioPin<4>,
-    btn_top_left: GpioPin<25>,
-    btn_top_right: GpioPin<35>,
+/// The display while we're not drawing to it.
+enum SleepingDisplay<'a> {
+    Uninitialized(Display<'a, gdeh0154d67_async::Uninitialized>),
+    Hibernating(Display<'a, gdeh0154d67_async::Hibernating<gdeh0154d67_async::DeepSleepMode1>>),
 }
 
-pub enum WakeupCause {
-    /// First boot or manual reset from serial monitor
-    Reset,
-
-    /// The external RTC told us to wake up
-    ExternalRtcAlarm,
+/// Kept in RTC slow memory across deep sleep. It holds garbage after a power
+/// cycle, which [Record] detects.
+#[ram(rtc_slow, persistent)]
+static mut PERSISTENT_STATE: Record<32> = Record::new();
 
-    /// One (or more?) of the buttons was pressed
-    ButtonPress(WakeupButtons),
-
-    /// Probably shouldn't happen
-    // TODO turn into Error?
-    Unknown(SleepSource),
+/// GPIO pins used to wake up the device during sleep
+pub struct WakeupPins {
+    external_rtc: GpioPin<27>,
+    buttons: ButtonPins,
 }
 
 pub struct Watchy<'a> {
-    pub display: Display<'a>,
+    display: Option<SleepingDisplay<'a>>,
     pub external_rtc: pcf8563_async::PCF8563<I2cBusDevice<'a>>,
     pub sensor: bma423_async::BMA423<I2cBusDevice<'a>, embassy_time::Delay>,
     pub vibration_motor: VibrationMotor<'a>,
     pub battery: Battery<'a, embassy_time::Delay>,
     pub draw_buffer: DrawBuffer,
+    /// When [app::refresh_display] does a full refresh instead of a partial one.
+    pub refresh_policy: RefreshPolicy,
+    refresh_state: RefreshState,
     lpwr: LPWR,
     wakeup_pins: WakeupPins,
 }
 
-impl Watchy<'_> {
+impl<'a> Watchy<'a> {
     pub fn init() -> Result<Self, Error> {
         let config = esp_hal::Config::default();
         let peripherals = esp_hal::init(config);
@@ -195,10 +216,12 @@ impl Watchy<'_> {
         // Initialize wakeup pins
         let wakeup_pins = WakeupPins {
             external_rtc: peripherals.GPIO27,
-            btn_bottom_left: peripherals.GPIO26,
-            btn_bottom_right: peripherals.GPIO4,
-            btn_top_left: peripherals.GPIO25,
-            btn_top_right: peripherals.GPIO35,
+            buttons: ButtonPins::new(
+                peripherals.GPIO25,
+                peripherals.GPIO35,
+                peripherals.GPIO26,
+                peripherals.GPIO4,
+            ),
         };
 
         let lpwr: LPWR = peripherals.LPWR;
@@ -207,64 +230,140 @@ impl Watchy<'_> {
 
         let draw_buffer = DrawBuffer::empty();
 
+        let refresh_state = match esp_hal::reset::wakeup_cause() {
+            SleepSource::Undefined => RefreshState::default(),
+            // SAFETY: Nothing else runs yet, and any bit pattern is a valid Record.
+            _ => unsafe { (*core::ptr::addr_of!(PERSISTENT_STATE)).load_or_default() },
+        };
+
         Ok(Watchy {
-            display: gdeh0154d67,
+            display: Some(SleepingDisplay::Uninitialized(gdeh0154d67)),
             external_rtc: pcf8563,
             sensor: bma423,
             vibration_motor,
             battery,
             draw_buffer,
+            refresh_policy: RefreshPolicy::default(),
+            refresh_state,
             lpwr,
             wakeup_pins,
         })
     }
 
+    /// Show `face` for whatever woke the watch up, then sleep until the
+    /// next wakeup it asks for or a button press.
+    pub async fn run<F: WatchFace>(mut self, face: &mut F) -> ! {
+        let cause = self.get_wakeup_cause();
+        defmt::println!("wakeup cause: {}", defmt::Debug2Format(&cause));
+
+        if cause == WakeupCause::Reset {
+            // It's only needed to measure the crystal, and draws power.
+            if let Err(error) = self
+                .external_rtc
+                .set_clock_output(pcf8563_async::ClockOutput::Disabled)
+                .await
+            {
+                defmt::warn!(
+                    "failed to disable the clock output: {}",
+                    defmt::Debug2Format(&error)
+                );
+            }
+        }
+
+        match app::handle_wakeup(&mut self, face, cause).await {
+            Ok(()) => {}
+            Err(error) => {
+                defmt::println!(
+                    "failed to handle the wakeup: {}",
+                    defmt::Debug2Format(&error)
+                );
+            }
+        }
+
+        defmt::println!("sleep");
+        self.sleep_deep()
+    }
+
     pub fn get_wakeup_cause(&self) -> WakeupCause {
         match esp_hal::reset::wakeup_cause() {
             SleepSource::Undefined => WakeupCause::Reset,
             SleepSource::Ext0 => WakeupCause::ExternalRtcAlarm,
             SleepSource::Ext1 => {
                 let buttons = WakeupButtons::from_wakeup_status(&self.lpwr);
-                WakeupCause::ButtonPress(buttons)
+                WakeupCause::ButtonPress(buttons.into())
             }
-            cause => WakeupCause::Unknown(cause),
+            _ => WakeupCause::Unknown,
         }
     }
 
     pub fn sleep_deep(&mut self) -> ! {
+        // SAFETY: We're single threaded and nothing holds a reference to it.
+        let record = unsafe { &mut *core::ptr::addr_of_mut!(PERSISTENT_STATE) };
+        if record.store(&self.refresh_state).is_err() {
+            defmt::warn!("persistent state doesn't fit, dropping it");
+        }
+
         let mut rtc = Rtc::new(&mut self.lpwr);
 
-        rtc.sleep_deep(&[
-            &Ext0WakeupSource::new(
-                &mut self.wakeup_pins.external_rtc,
-                esp_hal::rtc_cntl::sleep::WakeupLevel::Low,
-            ),
-            &Ext1WakeupSource::new(
-                &mut [
-                    &mut self.wakeup_pins.btn_bottom_left,
-                    &mut self.wakeup_pins.btn_bottom_right,
-                    &mut self.wakeup_pins.btn_top_left,
-                    &mut self.wakeup_pins.btn_top_right,
-                ],
-                esp_hal::rtc_cntl::sleep::WakeupLevel::High,
-            ),
-        ])
+        // The default deep sleep config powers down the RTC slow memory,
+        // which would lose the persistent state.
+        let mut config = RtcSleepConfig::deep();
+        config.set_rtc_slowmem_pd_en(false);
+
+        let ext0 = Ext0WakeupSource::new(&mut self.wakeup_pins.external_rtc, WakeupLevel::Low);
+        let buttons = &mut self.wakeup_pins.buttons;
+        let mut pins: [&mut dyn RtcPin; 4] = [
+            &mut buttons.bottom_left,
+            &mut buttons.bottom_right,
+            &mut buttons.top_left,
+            &mut buttons.top_right,
+        ];
+        let ext1 = Ext1WakeupSource::new(&mut pins, WakeupLevel::High);
+        rtc.sleep(&config, &[&ext0, &ext1]);
+        unreachable!()
     }
 
+    /// Draw the buffer with a full refresh. This doesn't touch the refresh
+    /// state, use [app::refresh_display] to pick the refresh and keep track
+    /// of it.
     pub async fn draw_buffer_to_display(&mut self) -> Result<(), Error> {
-        self.display.init().await?;
-        self.display
+        let mut display = self.wake_display().await?;
+        display
             .set_border_color(gdeh0154d67_async::BorderColor::White)
             .await?;
 
-        self.display.set_partial_ram_area(0, 0, 200, 200).await?;
-        self.display
-            .write_image_data(self.draw_buffer.buffer())
+        display.set_address_direction(self.draw_buffer.data_entry_mode());
+        display.set_partial_ram_area(0, 0, 200, 200).await?;
+        display.write_image_data(self.draw_buffer.buffer()).await?;
+        self.set_display_temperature(&mut display).await?;
+        display
+            .update_display(
+                gdeh0154d67_async::DisplayUpdateSequence::WATCHY_UPDATE_FULL
+                    | gdeh0154d67_async::DisplayUpdateSequence::DISABLE_ANALOG
+                    | gdeh0154d67_async::DisplayUpdateSequence::DISABLE_CLOCK_SIGNAL,
+                None,
+            )
             .await?;
-        self.display
-            .select_temperature_sensor(gdeh0154d67_async::TemperatureSensor::Internal)
+
+        // Partial updates compare against the RED RAM, so keep it in sync.
+        display.set_partial_ram_area(0, 0, 200, 200).await?;
+        display
+            .write_previous_image_data(self.draw_buffer.buffer())
+            .await?;
+
+        self.hibernate_display(display).await
+    }
+
+    /// Clear the display to white without sending an image over SPI.
+    pub async fn clear_display(&mut self) -> Result<(), Error> {
+        let mut display = self.wake_display().await?;
+        display
+            .set_border_color(gdeh0154d67_async::BorderColor::White)
             .await?;
-        self.display
+
+        display.clear().await?;
+        self.set_display_temperature(&mut display).await?;
+        display
             .update_display(
                 gdeh0154d67_async::DisplayUpdateSequence::WATCHY_UPDATE_FULL
                     | gdeh0154d67_async::DisplayUpdateSequence::DISABLE_ANALOG
@@ -273,10 +372,230 @@ impl Watchy<'_> {
             )
             .await?;
 
-        self.display.hibernate().await?;
+        self.hibernate_display(display).await
+    }
+
+    /// Like [Self::draw_buffer_to_display], but only redraws the pixels that
+    /// changed, so the panel doesn't flash.
+    ///
+    /// The frame on the display is still in its RED RAM, even after deep
+    /// sleep since it's hibernated in [gdeh0154d67_async::DeepSleepMode1],
+    /// so only the new frame is sent and the display compares the two.
+    pub async fn draw_buffer_to_display_partial(&mut self) -> Result<(), Error> {
+        let mut display = self.wake_display().await?;
+        display
+            .set_border_color(gdeh0154d67_async::BorderColor::White)
+            .await?;
+        self.set_display_temperature(&mut display).await?;
+
+        display.set_address_direction(self.draw_buffer.data_entry_mode());
+        display.set_partial_ram_area(0, 0, 200, 200).await?;
+        display.write_image_data(self.draw_buffer.buffer()).await?;
+        display
+            .update_display(
+                gdeh0154d67_async::DisplayUpdateSequence::WATCHY_UPDATE_PARTIAL,
+                None,
+            )
+            .await?;
+
+        // This frame is the one to compare against next time.
+        display.set_partial_ram_area(0, 0, 200, 200).await?;
+        display
+            .write_previous_image_data(self.draw_buffer.buffer())
+            .await?;
+
+        display.watchy_power_off().await?;
+
+        self.hibernate_display(display).await
+    }
+
+    /// Draw a grayscale buffer to the display. This always does a full refresh.
+    pub async fn draw_gray_buffer_to_display(
+        &mut self,
+        buffer: &Gray2DrawBuffer,
+    ) -> Result<(), Error> {
+        let mut display = self.wake_display().await?;
+        display
+            .set_border_color(gdeh0154d67_async::BorderColor::White)
+            .await?;
+
+        display.set_address_direction(gdeh0154d67_async::DataEntryMode::DEFAULT);
+        display
+            .update_gray2(
+                buffer.buffer(),
+                &gdeh0154d67_async::WaveformLut::gray2(),
+                gdeh0154d67_async::LutVoltages::gray2(),
+            )
+            .await?;
+
+        let hour = self.current_hour().await;
+        // The temperature doesn't matter here, the waveform is always the same.
+        self.refresh_state.record_full_refresh(hour, None);
+        // The RAMs now hold the gray planes, which a partial refresh can't
+        // compare against.
+        self.refresh_state.record_frame(None);
+
+        self.hibernate_display(display).await
+    }
+
+    /// The display's own sensor sits behind the glass, so give it the
+    /// temperature from the accelerometer instead when there is one,
+    /// to get the waveform that matches the weather outside.
+    async fn set_display_temperature(
+        &mut self,
+        display: &mut Display<'a, gdeh0154d67_async::Awake>,
+    ) -> Result<(), Error> {
+        let temperature = self.sensor.temperature_celsius().await.ok().flatten();
+        match temperature {
+            Some(temperature) => display.write_temperature(temperature).await?,
+            None => {
+                display
+                    .select_temperature_sensor(gdeh0154d67_async::TemperatureSensor::Internal)
+                    .await?
+            }
+        }
 
         Ok(())
     }
+
+    /// Only used to spread the full refreshes out, like [Clock::time], so
+    /// it's still good after the clock lost the time.
+    async fn current_hour(&mut self) -> Option<u8> {
+        self.external_rtc
+            .read_time_unchecked()
+            .await
+            .ok()
+            .map(|time| time.hour())
+    }
+
+    /// Take the display out of its sleeping state. If anything fails before
+    /// it's handed back to [Self::hibernate_display] the driver is lost.
+    ///
+    /// After deep sleep the driver starts out uninitialized, but the display
+    /// was hibernated in mode 1 so its RAM still holds the last frame.
+    async fn wake_display(&mut self) -> Result<Display<'a, gdeh0154d67_async::Awake>, Error> {
+        let display = match self.display.take() {
+            Some(SleepingDisplay::Uninitialized(display)) => display.init().await?,
+            Some(SleepingDisplay::Hibernating(display)) => display.wake().await?,
+            None => return Err(Error::DisplayUnavailable),
+        };
+
+        Ok(display)
+    }
+
+    async fn hibernate_display(
+        &mut self,
+        display: Display<'a, gdeh0154d67_async::Awake>,
+    ) -> Result<(), Error> {
+        let display = display.hibernate().await?;
+        self.display = Some(SleepingDisplay::Hibernating(display));
+
+        Ok(())
+    }
+}
+
+type I2cError = I2cDeviceError<i2c::master::Error>;
+
+impl Clock for Watchy<'_> {
+    type Error = pcf8563_async::Error<I2cError>;
+
+    async fn time(&mut self) -> Result<time::Time, Self::Error> {
+        self.external_rtc.read_time_unchecked().await
+    }
+
+    async fn time_lost(&mut self) -> Result<bool, Self::Error> {
+        self.external_rtc.is_clock_integrity_lost().await
+    }
+
+    async fn set_alarm(&mut self, time: time::Time) -> Result<(), Self::Error> {
+        let alarm = pcf8563_async::AlarmConfig {
+            minute: Some(time.minute()),
+            hour: Some(time.hour()),
+            ..Default::default()
+        };
+        self.external_rtc.set_alarm(&alarm).await?;
+        if self.external_rtc.read_alarm().await? != alarm {
+            defmt::warn!("the alarm wasn't set");
+        }
+
+        self.external_rtc.enable_alarm().await
+    }
+
+    async fn disable_alarm(&mut self) -> Result<(), Self::Error> {
+        self.external_rtc.disable_alarm().await
+    }
+}
+
+impl DisplaySink for Watchy<'_> {
+    type Error = Error;
+
+    fn draw_buffer(&mut self) -> &mut DrawBuffer {
+        &mut self.draw_buffer
+    }
+
+    fn refresh_policy(&self) -> RefreshPolicy {
+        self.refresh_policy
+    }
+
+    fn refresh_state(&mut self) -> &mut RefreshState {
+        &mut self.refresh_state
+    }
+
+    async fn clear(&mut self) -> Result<(), Error> {
+        self.clear_display().await
+    }
+
+    async fn show(&mut self, refresh: Refresh) -> Result<(), Error> {
+        match refresh {
+            Refresh::Full => self.draw_buffer_to_display().await,
+            Refresh::Partial => self.draw_buffer_to_display_partial().await,
+        }
+    }
+}
+
+impl Accelerometer for Watchy<'_> {
+    type Error = bma423_async::Error<I2cError>;
+
+    async fn initialize(&mut self) -> Result<(), Self::Error> {
+        self.sensor.initialize().await
+    }
+
+    async fn temperature_celsius(&mut self) -> Result<Option<i16>, Self::Error> {
+        self.sensor.temperature_celsius().await
+    }
+
+    async fn acceleration(&mut self) -> Result<(u16, u16, u16), Self::Error> {
+        self.sensor.accelerometer_xyz().await
+    }
+
+    async fn step_count(&mut self) -> Result<u32, Self::Error> {
+        self.sensor.step_count().await
+    }
+}
+
+impl hal::Battery for Watchy<'_> {
+    async fn voltage(&mut self) -> f32 {
+        self.battery.voltage().await
+    }
+}
+
+impl ButtonInput for Watchy<'_> {
+    fn pressed(&mut self) -> Buttons {
+        self.wakeup_pins.buttons.pressed()
+    }
+}
+
+impl Haptics for Watchy<'_> {
+    async fn vibrate(&mut self, times: u8, interval_ms: u32) {
+        if times > 0 {
+            self.vibration_motor
+                .vibrate_linear(
+                    times,
+                    embassy_time::Duration::from_millis(interval_ms.into()),
+                )
+                .await;
+        }
+    }
 }
 
 fn init_dma_buffers() -> (DmaRxBuf, DmaTxBuf) {
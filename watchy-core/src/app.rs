@@ -0,0 +1,383 @@
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::DrawTarget};
+use unwrap_infallible::UnwrapInfallible as _;
+
+use crate::{
+    face::{FaceData, WatchFace},
+    hal::{Accelerometer, Battery, Clock, DisplaySink, Refresh, WakeupCause},
+};
+
+#[derive(Debug)]
+pub enum Error<C, A, D> {
+    Clock(C),
+    Accelerometer(A),
+    Display(D),
+}
+
+type WakeupError<W> =
+    Error<<W as Clock>::Error, <W as Accelerometer>::Error, <W as DisplaySink>::Error>;
+
+// TODO this doesn't seem to be correct. Check the battery
+const EMPTY_BATTERY_VOLTAGE: f32 = 2.75;
+const FULL_BATTERY_VOLTAGE: f32 = 3.7;
+
+/// Rough charge left in a battery at `voltage`, from 0 to 100.
+pub fn battery_percentage(voltage: f32) -> u8 {
+    let percentage =
+        (voltage - EMPTY_BATTERY_VOLTAGE) / (FULL_BATTERY_VOLTAGE - EMPTY_BATTERY_VOLTAGE) * 100.0;
+
+    percentage.clamp(0.0, 100.0) as u8
+}
+
+/// Show `face` for whatever woke the watch up, and set the alarm for the
+/// next time it wants to be shown, before going back to sleep.
+///
+/// The alarm is set before drawing, so a broken display doesn't keep the
+/// watch from waking up again. After the clock lost the time, the alarm is
+/// still set from the time it counts, and the face asks for it to be set.
+pub async fn handle_wakeup<W, F>(
+    watch: &mut W,
+    face: &mut F,
+    cause: WakeupCause,
+) -> Result<(), WakeupError<W>>
+where
+    W: Clock + Accelerometer + Battery + DisplaySink,
+    F: WatchFace,
+{
+    if cause == WakeupCause::Reset {
+        watch.initialize().await.map_err(Error::Accelerometer)?;
+    }
+
+    if let WakeupCause::ButtonPress(buttons) = cause {
+        face.on_button(buttons);
+    }
+
+    let time = watch.time().await.map_err(Error::Clock)?;
+    let time_lost = watch.time_lost().await.map_err(Error::Clock)?;
+    let voltage = watch.voltage().await;
+    let steps = watch.step_count().await.map_err(Error::Accelerometer)?;
+
+    match face.next_wakeup(time) {
+        Some(next) => watch.set_alarm(next).await,
+        None => watch.disable_alarm().await,
+    }
+    .map_err(Error::Clock)?;
+
+    let temperature = watch.temperature_celsius().await.ok().flatten();
+
+    let buffer = watch.draw_buffer();
+    buffer.clear(BinaryColor::Off).unwrap_infallible();
+    let blank = buffer.checksum();
+
+    if let WakeupCause::Reset | WakeupCause::Unknown = cause {
+        // Get rid of whatever was left on the display before the reset.
+        watch.clear().await.map_err(Error::Display)?;
+        let state = watch.refresh_state();
+        state.record_full_refresh(Some(time.hour()), temperature);
+        // The display is white now, so the face can go over it with a
+        // partial refresh instead of flashing the panel again.
+        state.record_frame(Some(blank));
+    }
+
+    let data = FaceData {
+        time,
+        battery_percentage: battery_percentage(voltage),
+        steps,
+    };
+    let buffer = watch.draw_buffer();
+    if time_lost {
+        face.render_time_lost(&data, buffer);
+    } else {
+        face.render(&data, buffer);
+    }
+    refresh_display(watch, Some(time.hour()), temperature)
+        .await
+        .map_err(Error::Display)?;
+
+    Ok(())
+}
+
+/// Show the draw buffer with a partial refresh, or a full one when the
+/// refresh policy asks for it, and keep track of it in the refresh state.
+/// Nothing is redrawn if the display already shows the same frame.
+pub async fn refresh_display<W: DisplaySink>(
+    watch: &mut W,
+    hour: Option<u8>,
+    temperature: Option<i16>,
+) -> Result<(), W::Error> {
+    let frame = watch.draw_buffer().checksum();
+    let policy = watch.refresh_policy();
+    let Some(refresh) = watch
+        .refresh_state()
+        .next_refresh(&policy, hour, temperature, frame)
+    else {
+        return Ok(());
+    };
+
+    // If drawing fails halfway, the display could show anything.
+    watch.refresh_state().record_frame(None);
+    watch.show(refresh).await?;
+
+    let state = watch.refresh_state();
+    match refresh {
+        Refresh::Full => state.record_full_refresh(hour, temperature),
+        Refresh::Partial => state.record_partial_refresh(hour),
+    }
+    state.record_frame(Some(frame));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_futures::block_on;
+    use time::Time;
+
+    use super::{battery_percentage, handle_wakeup, Error};
+    use crate::{
+        draw_buffer::DrawBuffer,
+        face::{self, FaceData, SimpleFace, WatchFace},
+        fake::{FakeError, FakeWatch},
+        hal::{Buttons, Refresh, WakeupCause},
+    };
+
+    fn time(hour: u8, minute: u8) -> Time {
+        Time::from_hms(hour, minute, 0).unwrap()
+    }
+
+    fn watch(hour: u8, minute: u8) -> FakeWatch {
+        FakeWatch::new(time(hour, minute))
+    }
+
+    /// Only wakes up on button presses, and remembers them.
+    #[derive(Default)]
+    struct ButtonFace {
+        buttons: Buttons,
+    }
+
+    impl WatchFace for ButtonFace {
+        fn render(&mut self, _data: &FaceData, _target: &mut DrawBuffer) {}
+
+        fn on_button(&mut self, buttons: Buttons) {
+            self.buttons |= buttons;
+        }
+
+        fn next_wakeup(&self, _now: Time) -> Option<Time> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_battery_percentage() {
+        assert_eq!(battery_percentage(3.7), 100);
+        assert_eq!(battery_percentage(4.2), 100);
+        assert_eq!(battery_percentage(2.75), 0);
+        assert_eq!(battery_percentage(1.0), 0);
+        assert_eq!(battery_percentage(3.3), 57);
+    }
+
+    #[test]
+    fn test_default_next_wakeup() {
+        let face = SimpleFace::default();
+        assert_eq!(
+            face.next_wakeup(Time::from_hms(10, 9, 42).unwrap()),
+            Some(time(10, 10))
+        );
+        assert_eq!(face.next_wakeup(time(23, 59)), Some(time(0, 0)));
+    }
+
+    #[test]
+    fn test_reset_draws_the_time() {
+        let mut watch = watch(10, 9);
+        watch.steps = 1234;
+        watch.voltage = 3.7;
+
+        block_on(handle_wakeup(
+            &mut watch,
+            &mut SimpleFace::default(),
+            WakeupCause::Reset,
+        ))
+        .unwrap();
+
+        assert!(watch.accelerometer_initialized);
+        assert_eq!(watch.alarm, Some(time(10, 10)));
+        assert_eq!(watch.clear_count, 1);
+        assert_eq!(watch.show_count, 1);
+
+        let mut expected = DrawBuffer::empty();
+        let data = FaceData {
+            time: watch.time,
+            battery_percentage: 100,
+            steps: 1234,
+        };
+        face::draw_time(&data, &mut expected).unwrap();
+        assert_eq!(watch.shown.unwrap().buffer(), expected.buffer());
+    }
+
+    #[test]
+    fn test_reset_does_one_full_refresh() {
+        let mut watch = watch(10, 9);
+
+        block_on(handle_wakeup(
+            &mut watch,
+            &mut SimpleFace::default(),
+            WakeupCause::Reset,
+        ))
+        .unwrap();
+
+        // Clearing the display is the full refresh, the face is drawn over
+        // it.
+        assert_eq!(watch.clear_count, 1);
+        assert_eq!(watch.show_count, 1);
+        assert_eq!(watch.last_refresh, Some(Refresh::Partial));
+        assert_eq!(watch.refresh_state.partial_refreshes(), 1);
+    }
+
+    #[test]
+    fn test_rtc_alarm_redraws_without_clearing() {
+        let mut watch = watch(10, 59);
+
+        block_on(handle_wakeup(
+            &mut watch,
+            &mut SimpleFace::default(),
+            WakeupCause::ExternalRtcAlarm,
+        ))
+        .unwrap();
+
+        assert!(!watch.accelerometer_initialized);
+        assert_eq!(watch.alarm, Some(time(11, 0)));
+        assert_eq!(watch.clear_count, 0);
+        assert_eq!(watch.show_count, 1);
+    }
+
+    #[test]
+    fn test_unchanged_face_isnt_redrawn() {
+        let mut watch = watch(10, 9);
+        let mut face = SimpleFace::default();
+
+        for _ in 0..2 {
+            block_on(handle_wakeup(
+                &mut watch,
+                &mut face,
+                WakeupCause::ExternalRtcAlarm,
+            ))
+            .unwrap();
+        }
+
+        assert_eq!(watch.show_count, 1);
+    }
+
+    #[test]
+    fn test_refreshes_follow_the_policy() {
+        let mut watch = watch(10, 58);
+        let mut face = SimpleFace::default();
+        let mut wake_up_at = |watch: &mut FakeWatch, hour, minute| {
+            watch.time = time(hour, minute);
+            block_on(handle_wakeup(
+                watch,
+                &mut face,
+                WakeupCause::ExternalRtcAlarm,
+            ))
+            .unwrap();
+            watch.last_refresh
+        };
+
+        // Nothing is known about what the display shows yet.
+        assert_eq!(wake_up_at(&mut watch, 10, 58), Some(Refresh::Full));
+        assert_eq!(wake_up_at(&mut watch, 10, 59), Some(Refresh::Partial));
+        assert_eq!(watch.refresh_state.partial_refreshes(), 1);
+        // The hour changed.
+        assert_eq!(wake_up_at(&mut watch, 11, 0), Some(Refresh::Full));
+        assert_eq!(watch.refresh_state.partial_refreshes(), 0);
+    }
+
+    #[test]
+    fn test_button_press_is_passed_to_the_face() {
+        let mut watch = watch(10, 9);
+        watch.alarm = Some(time(10, 10));
+        let mut face = ButtonFace::default();
+
+        block_on(handle_wakeup(
+            &mut watch,
+            &mut face,
+            WakeupCause::ButtonPress(Buttons::TOP_LEFT | Buttons::BOTTOM_LEFT),
+        ))
+        .unwrap();
+
+        assert_eq!(face.buttons, Buttons::TOP_LEFT | Buttons::BOTTOM_LEFT);
+        assert_eq!(watch.alarm, None);
+        assert_eq!(watch.show_count, 1);
+    }
+
+    #[test]
+    fn test_button_press_shows_the_button_screen() {
+        let mut watch = watch(10, 9);
+
+        block_on(handle_wakeup(
+            &mut watch,
+            &mut SimpleFace::default(),
+            WakeupCause::ButtonPress(Buttons::TOP_RIGHT),
+        ))
+        .unwrap();
+
+        let mut expected = DrawBuffer::empty();
+        face::draw_button_pressed(&mut expected).unwrap();
+        assert_eq!(watch.shown.unwrap().buffer(), expected.buffer());
+    }
+
+    #[test]
+    fn test_alarm_is_set_when_the_display_fails() {
+        let mut watch = watch(10, 9);
+        watch.display_fails = true;
+
+        let result = block_on(handle_wakeup(
+            &mut watch,
+            &mut SimpleFace::default(),
+            WakeupCause::Unknown,
+        ));
+
+        assert!(matches!(result, Err(Error::Display(FakeError))));
+        assert_eq!(watch.alarm, Some(time(10, 10)));
+    }
+
+    #[test]
+    fn test_lost_time_asks_to_set_it() {
+        let mut watch = watch(10, 9);
+        watch.time_lost = true;
+        watch.steps = 1234;
+
+        block_on(handle_wakeup(
+            &mut watch,
+            &mut SimpleFace::default(),
+            WakeupCause::Reset,
+        ))
+        .unwrap();
+
+        // The clock still counts, so the watch keeps waking up.
+        assert_eq!(watch.alarm, Some(time(10, 10)));
+        assert_eq!(watch.show_count, 1);
+
+        let mut expected = DrawBuffer::empty();
+        let data = FaceData {
+            time: watch.time,
+            battery_percentage: 100,
+            steps: 1234,
+        };
+        face::draw_set_time(&data, &mut expected).unwrap();
+        assert_eq!(watch.shown.unwrap().buffer(), expected.buffer());
+    }
+
+    #[test]
+    fn test_clock_failure() {
+        let mut watch = watch(10, 9);
+        watch.clock_fails = true;
+
+        let result = block_on(handle_wakeup(
+            &mut watch,
+            &mut SimpleFace::default(),
+            WakeupCause::ButtonPress(Buttons::BOTTOM_RIGHT),
+        ));
+
+        assert!(matches!(result, Err(Error::Clock(FakeError))));
+        assert_eq!(watch.show_count, 0);
+    }
+}
@@ -0,0 +1,545 @@
+use core::convert::Infallible;
+
+use arrayvec::ArrayVec;
+use embedded_graphics::{
+    pixelcolor::{BinaryColor, Gray2},
+    prelude::{DrawTarget, GrayColor, OriginDimensions, Point, Size},
+    Pixel,
+};
+use gdeh0154d67_async::DataEntryMode;
+
+use crate::persist::Crc32;
+
+/// Width and height of the display in pixels.
+pub const WIDTH: usize = 200;
+
+/// Size in bytes of a [DrawBuffer].
+pub const BUFFER_SIZE: usize = WIDTH * WIDTH / 8;
+
+/// How many separate dirty regions are tracked before they get merged.
+pub const MAX_DIRTY_REGIONS: usize = 4;
+
+/// Area of the display RAM that changed since it was last drawn.
+/// `x` and `width` are multiples of 8, so it can be passed straight to
+/// [gdeh0154d67_async::GDEH0154D67::set_partial_ram_area].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirtyRegion {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl DirtyRegion {
+    fn right(&self) -> u16 {
+        self.x + self.width
+    }
+
+    fn bottom(&self) -> u16 {
+        self.y + self.height
+    }
+
+    fn area(&self) -> u32 {
+        self.width as u32 * self.height as u32
+    }
+
+    fn intersects(&self, other: &DirtyRegion) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    fn union(&self, other: &DirtyRegion) -> DirtyRegion {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        DirtyRegion {
+            x,
+            y,
+            width: self.right().max(other.right()) - x,
+            height: self.bottom().max(other.bottom()) - y,
+        }
+    }
+}
+
+/// Clockwise rotation of the image on the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    Rotate0,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+#[derive(Clone)]
+pub struct DrawBuffer {
+    buffer: [u8; BUFFER_SIZE],
+    rotation: Rotation,
+    mirrored: bool,
+    /// Disjoint regions of the buffer, not the display, that changed.
+    dirty: ArrayVec<DirtyRegion, MAX_DIRTY_REGIONS>,
+}
+
+impl DrawBuffer {
+    pub fn empty() -> Self {
+        DrawBuffer {
+            buffer: [0xFF; BUFFER_SIZE],
+            rotation: Rotation::Rotate0,
+            mirrored: false,
+            dirty: ArrayVec::new(),
+        }
+    }
+
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Rotate everything drawn from now on. This doesn't touch what is
+    /// already in the buffer, so clear it first.
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.rotation = rotation;
+    }
+
+    /// Mirror everything drawn from now on left to right, before rotating it.
+    pub fn set_mirrored(&mut self, mirrored: bool) {
+        self.mirrored = mirrored;
+    }
+
+    /// How the display should fill its RAM with [Self::buffer].
+    ///
+    /// Flipping upside down only changes the order of the rows, which the
+    /// display can do itself, so in that case the buffer is left unflipped.
+    /// Other orientations are handled while drawing.
+    pub fn data_entry_mode(&self) -> DataEntryMode {
+        if self.flipped_by_display() {
+            DataEntryMode::X_INCREMENT
+        } else {
+            DataEntryMode::X_INCREMENT | DataEntryMode::Y_INCREMENT
+        }
+    }
+
+    /// Checksum of the image as the display shows it, to tell whether a
+    /// frame is any different from the one already on the display.
+    pub fn checksum(&self) -> u32 {
+        let mut crc = Crc32::new();
+        crc.update(&[self.data_entry_mode().bits()]);
+        crc.update(&self.buffer);
+        crc.finish()
+    }
+
+    /// Get the areas of the display that changed since the last call,
+    /// and start tracking changes from scratch.
+    ///
+    /// The changes are against what was drawn into this buffer before, not
+    /// against what the display shows. The firmware draws the buffer from
+    /// scratch after every deep sleep, so it always sends the whole frame
+    /// instead, and these are only of use while the display stays awake.
+    pub fn take_dirty_regions(&mut self) -> ArrayVec<DirtyRegion, MAX_DIRTY_REGIONS> {
+        let mut regions = core::mem::take(&mut self.dirty);
+        if self.flipped_by_display() {
+            for region in regions.iter_mut() {
+                region.y = WIDTH as u16 - region.bottom();
+            }
+        }
+
+        regions
+    }
+
+    /// Row `y` of the image as the display shows it, one bit per pixel
+    /// with the most significant bit first, where 1 is white.
+    pub fn row(&self, y: usize) -> &[u8] {
+        let y = if self.flipped_by_display() {
+            WIDTH - 1 - y
+        } else {
+            y
+        };
+
+        &self.buffer[y * WIDTH / 8..][..WIDTH / 8]
+    }
+
+    fn add_dirty_region(&mut self, mut region: DirtyRegion) {
+        loop {
+            // Keep the regions disjoint so no part of the display is sent twice.
+            while let Some(i) = self.dirty.iter().position(|r| r.intersects(&region)) {
+                region = region.union(&self.dirty.swap_remove(i));
+            }
+
+            if !self.dirty.is_full() {
+                self.dirty.push(region);
+                return;
+            }
+
+            // Out of room, merge with whichever region grows the least.
+            let i = (0..self.dirty.len())
+                .min_by_key(|&i| self.dirty[i].union(&region).area() - self.dirty[i].area())
+                .unwrap();
+            region = region.union(&self.dirty.swap_remove(i));
+        }
+    }
+
+    fn flipped_by_display(&self) -> bool {
+        self.mirrored && self.rotation == Rotation::Rotate180
+    }
+
+    /// Map a point in the rotated coordinate system to a pixel in the buffer.
+    fn buffer_position(&self, point: Point) -> Option<(usize, usize)> {
+        let max = WIDTH as i32 - 1;
+        let (x, y) = match point.into() {
+            (x @ 0..=199, y @ 0..=199) => (x, y),
+            _ => return None,
+        };
+
+        if self.flipped_by_display() {
+            return Some((x as usize, y as usize));
+        }
+
+        let x = if self.mirrored { max - x } else { x };
+        let (x, y) = match self.rotation {
+            Rotation::Rotate0 => (x, y),
+            Rotation::Rotate90 => (max - y, x),
+            Rotation::Rotate180 => (max - x, max - y),
+            Rotation::Rotate270 => (y, max - x),
+        };
+
+        Some((x as usize, y as usize))
+    }
+}
+
+impl OriginDimensions for DrawBuffer {
+    fn size(&self) -> embedded_graphics::prelude::Size {
+        Size {
+            width: WIDTH as u32,
+            height: WIDTH as u32,
+        }
+    }
+}
+
+impl DrawTarget for DrawBuffer {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let mut changed: Option<(usize, usize, usize, usize)> = None;
+
+        for Pixel(pos, color) in pixels.into_iter() {
+            if let Some((x, y)) = self.buffer_position(pos) {
+                let index = x + y * WIDTH;
+                let old = self.buffer[index / 8];
+                self.buffer[index / 8] &= !(1 << (7 - (index % 8)));
+                if color.is_off() {
+                    self.buffer[index / 8] |= 1 << (7 - (index % 8));
+                }
+
+                if self.buffer[index / 8] != old {
+                    changed = Some(match changed {
+                        Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                        None => (x, y, x, y),
+                    });
+                }
+            }
+        }
+
+        if let Some((x0, y0, x1, y1)) = changed {
+            self.add_dirty_region(DirtyRegion {
+                x: (x0 / 8 * 8) as u16,
+                y: y0 as u16,
+                width: ((x1 / 8 - x0 / 8 + 1) * 8) as u16,
+                height: (y1 - y0 + 1) as u16,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Draw buffer with 4 levels of gray, to be drawn with
+/// `Watchy::draw_gray_buffer_to_display` in the firmware.
+pub struct Gray2DrawBuffer([u8; WIDTH * WIDTH / 4]);
+
+impl Gray2DrawBuffer {
+    pub fn empty() -> Self {
+        Gray2DrawBuffer([0xFF; WIDTH * WIDTH / 4])
+    }
+
+    pub fn buffer(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl OriginDimensions for Gray2DrawBuffer {
+    fn size(&self) -> embedded_graphics::prelude::Size {
+        Size {
+            width: WIDTH as u32,
+            height: WIDTH as u32,
+        }
+    }
+}
+
+impl DrawTarget for Gray2DrawBuffer {
+    type Color = Gray2;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels.into_iter() {
+            if let (x @ 0..=199, y @ 0..=199) = pos.into() {
+                let index = x as usize + y as usize * WIDTH;
+                let shift = 6 - (index % 4) * 2;
+                self.0[index / 4] &= !(0b11 << shift);
+                self.0[index / 4] |= color.luma() << shift;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::{
+        pixelcolor::BinaryColor,
+        prelude::{Point, Primitive, Size},
+        primitives::{PrimitiveStyle, Rectangle},
+        Drawable,
+    };
+
+    use gdeh0154d67_async::DataEntryMode;
+
+    use super::{DirtyRegion, DrawBuffer, Rotation, MAX_DIRTY_REGIONS};
+
+    const ORIENTATIONS: [(Rotation, bool); 8] = [
+        (Rotation::Rotate0, false),
+        (Rotation::Rotate0, true),
+        (Rotation::Rotate90, false),
+        (Rotation::Rotate90, true),
+        (Rotation::Rotate180, false),
+        (Rotation::Rotate180, true),
+        (Rotation::Rotate270, false),
+        (Rotation::Rotate270, true),
+    ];
+
+    fn oriented(rotation: Rotation, mirrored: bool) -> DrawBuffer {
+        let mut buffer = DrawBuffer::empty();
+        buffer.set_rotation(rotation);
+        buffer.set_mirrored(mirrored);
+        buffer
+    }
+
+    fn fill(buffer: &mut DrawBuffer, x: i32, y: i32, width: u32, height: u32, color: BinaryColor) {
+        Rectangle::new(Point::new(x, y), Size::new(width, height))
+            .into_styled(PrimitiveStyle::with_fill(color))
+            .draw(buffer)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_empty_buffer_is_clean() {
+        let mut buffer = DrawBuffer::empty();
+        assert!(buffer.take_dirty_regions().is_empty());
+    }
+
+    #[test]
+    fn test_dirty_region_is_byte_aligned() {
+        let mut buffer = DrawBuffer::empty();
+        fill(&mut buffer, 10, 20, 5, 3, BinaryColor::On);
+
+        let regions = buffer.take_dirty_regions();
+        assert_eq!(
+            regions.as_slice(),
+            [DirtyRegion {
+                x: 8,
+                y: 20,
+                width: 8,
+                height: 3
+            }]
+        );
+        assert!(buffer.take_dirty_regions().is_empty());
+    }
+
+    #[test]
+    fn test_unchanged_pixels_are_clean() {
+        let mut buffer = DrawBuffer::empty();
+        fill(&mut buffer, 0, 0, 50, 50, BinaryColor::Off);
+        assert!(buffer.take_dirty_regions().is_empty());
+    }
+
+    #[test]
+    fn test_overlapping_regions_are_merged() {
+        let mut buffer = DrawBuffer::empty();
+        fill(&mut buffer, 0, 0, 16, 16, BinaryColor::On);
+        fill(&mut buffer, 8, 8, 16, 16, BinaryColor::On);
+        fill(&mut buffer, 100, 100, 8, 8, BinaryColor::On);
+
+        let regions = buffer.take_dirty_regions();
+        assert_eq!(regions.len(), 2);
+        assert!(regions.contains(&DirtyRegion {
+            x: 0,
+            y: 0,
+            width: 24,
+            height: 24
+        }));
+        assert!(regions.contains(&DirtyRegion {
+            x: 100 / 8 * 8,
+            y: 100,
+            width: 16,
+            height: 8
+        }));
+    }
+
+    #[test]
+    fn test_regions_are_merged_when_full() {
+        let mut buffer = DrawBuffer::empty();
+        for i in 0..MAX_DIRTY_REGIONS as i32 + 2 {
+            fill(&mut buffer, i * 32, i * 32, 8, 8, BinaryColor::On);
+        }
+
+        let regions = buffer.take_dirty_regions();
+        assert_eq!(regions.len(), MAX_DIRTY_REGIONS);
+        for i in 0..MAX_DIRTY_REGIONS as u16 + 2 {
+            let drawn = DirtyRegion {
+                x: i * 32,
+                y: i * 32,
+                width: 8,
+                height: 8,
+            };
+            assert!(regions.iter().any(|r| r.union(&drawn) == *r));
+        }
+    }
+
+    #[test]
+    fn test_dirty_region_when_flipped_by_display() {
+        let mut buffer = DrawBuffer::empty();
+        buffer.set_rotation(Rotation::Rotate180);
+        buffer.set_mirrored(true);
+        fill(&mut buffer, 0, 0, 16, 2, BinaryColor::On);
+
+        // The top of the image is at the bottom of the display RAM.
+        assert_eq!(
+            buffer.take_dirty_regions().as_slice(),
+            [DirtyRegion {
+                x: 0,
+                y: 198,
+                width: 16,
+                height: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_row_when_flipped_by_display() {
+        let mut buffer = DrawBuffer::empty();
+        buffer.set_rotation(Rotation::Rotate180);
+        buffer.set_mirrored(true);
+        fill(&mut buffer, 0, 0, 8, 1, BinaryColor::On);
+
+        // The top of the image is at the bottom of the display, but the
+        // buffer leaves flipping the rows to the display.
+        assert_eq!(buffer.buffer()[..2], [0x00, 0xff]);
+        assert_eq!(buffer.row(199)[..2], [0x00, 0xff]);
+        assert_eq!(buffer.row(0)[..2], [0xff, 0xff]);
+    }
+
+    #[test]
+    fn test_buffer_position_per_orientation() {
+        // Where (10, 20) and the top right corner end up in the buffer.
+        let expected = [
+            ((10, 20), (199, 0)),
+            ((189, 20), (0, 0)),
+            ((179, 10), (199, 199)),
+            ((179, 189), (199, 0)),
+            ((189, 179), (0, 199)),
+            // Left to the display, which fills the rows bottom up.
+            ((10, 20), (199, 0)),
+            ((20, 189), (0, 0)),
+            ((20, 10), (0, 199)),
+        ];
+
+        for ((rotation, mirrored), (point, corner)) in ORIENTATIONS.into_iter().zip(expected) {
+            let buffer = oriented(rotation, mirrored);
+            assert_eq!(
+                buffer.buffer_position(Point::new(10, 20)),
+                Some(point),
+                "{rotation:?}, mirrored: {mirrored}"
+            );
+            assert_eq!(
+                buffer.buffer_position(Point::new(199, 0)),
+                Some(corner),
+                "{rotation:?}, mirrored: {mirrored}"
+            );
+            assert_eq!(buffer.buffer_position(Point::new(200, 0)), None);
+            assert_eq!(buffer.buffer_position(Point::new(0, -1)), None);
+        }
+    }
+
+    #[test]
+    fn test_data_entry_mode_per_orientation() {
+        for (rotation, mirrored) in ORIENTATIONS {
+            let expected = if rotation == Rotation::Rotate180 && mirrored {
+                DataEntryMode::X_INCREMENT
+            } else {
+                DataEntryMode::X_INCREMENT | DataEntryMode::Y_INCREMENT
+            };
+            assert_eq!(
+                oriented(rotation, mirrored).data_entry_mode(),
+                expected,
+                "{rotation:?}, mirrored: {mirrored}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_dirty_regions_per_orientation() {
+        // The region covering a 5x3 rectangle at (10, 20), in buffer
+        // coordinates, which is how the display RAM is addressed.
+        let expected = [
+            (8, 20, 3),
+            (184, 20, 3),
+            (176, 10, 5),
+            (176, 185, 5),
+            (184, 177, 3),
+            (8, 177, 3),
+            (16, 185, 5),
+            (16, 10, 5),
+        ];
+
+        for ((rotation, mirrored), (x, y, height)) in ORIENTATIONS.into_iter().zip(expected) {
+            let mut buffer = oriented(rotation, mirrored);
+            fill(&mut buffer, 10, 20, 5, 3, BinaryColor::On);
+
+            assert_eq!(
+                buffer.take_dirty_regions().as_slice(),
+                [DirtyRegion {
+                    x,
+                    y,
+                    width: 8,
+                    height
+                }],
+                "{rotation:?}, mirrored: {mirrored}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_checksum() {
+        let mut buffer = DrawBuffer::empty();
+        fill(&mut buffer, 10, 20, 5, 3, BinaryColor::On);
+        let mut same = DrawBuffer::empty();
+        fill(&mut same, 10, 20, 5, 3, BinaryColor::On);
+        assert_eq!(buffer.checksum(), same.checksum());
+
+        fill(&mut same, 0, 0, 1, 1, BinaryColor::On);
+        assert_ne!(buffer.checksum(), same.checksum());
+
+        // The same bytes, but shown upside down.
+        let mut flipped = oriented(Rotation::Rotate180, true);
+        fill(&mut flipped, 10, 20, 5, 3, BinaryColor::On);
+        assert_eq!(buffer.buffer(), flipped.buffer());
+        assert_ne!(buffer.checksum(), flipped.checksum());
+    }
+}
@@ -0,0 +1,195 @@
+use core::fmt::Write as _;
+
+use arrayvec::ArrayString;
+use embedded_graphics::{
+    mono_font::{
+        ascii::{FONT_10X20, FONT_6X10, FONT_9X18_BOLD},
+        MonoTextStyle,
+    },
+    pixelcolor::BinaryColor,
+    prelude::{DrawTarget, Point, Primitive, Size, Transform},
+    primitives::{Circle, PrimitiveStyle, Rectangle, Triangle},
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+    Drawable as _,
+};
+use time::{Duration, Time};
+use unwrap_infallible::UnwrapInfallible as _;
+
+use crate::{draw_buffer::DrawBuffer, hal::Buttons};
+
+/// Everything a watch face shows that has to be read from the hardware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaceData {
+    pub time: Time,
+
+    /// Charge left in the battery, from 0 to 100.
+    pub battery_percentage: u8,
+
+    pub steps: u32,
+}
+
+/// What the watch shows, and when it wakes up to update it.
+///
+/// On every wakeup the runtime calls [Self::on_button] if a button woke the
+/// watch up, sets the alarm to [Self::next_wakeup], redraws the display with
+/// [Self::render], and goes back to sleep. While the clock has lost the time
+/// it draws [Self::render_time_lost] instead.
+pub trait WatchFace {
+    /// Draw the face into a white buffer.
+    fn render(&mut self, data: &FaceData, target: &mut DrawBuffer);
+
+    /// Draw into a white buffer what to show while the time needs to be set,
+    /// since [FaceData::time] is wrong. By default that's [draw_set_time].
+    fn render_time_lost(&mut self, data: &FaceData, target: &mut DrawBuffer) {
+        draw_set_time(data, target).unwrap_infallible();
+    }
+
+    fn on_button(&mut self, _buttons: Buttons) {}
+
+    /// When to wake up next, or [None] to only wake up on a button press.
+    /// By default that's at the start of the next minute.
+    fn next_wakeup(&self, now: Time) -> Option<Time> {
+        let next = now + Duration::minutes(1);
+        Some(Time::from_hms(next.hour(), next.minute(), 0).unwrap())
+    }
+}
+
+/// Shows the time, or a test screen when woken up by a button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SimpleFace {
+    button_pressed: bool,
+}
+
+impl WatchFace for SimpleFace {
+    fn render(&mut self, data: &FaceData, target: &mut DrawBuffer) {
+        if self.button_pressed {
+            draw_button_pressed(target).unwrap_infallible();
+        } else {
+            draw_time(data, target).unwrap_infallible();
+        }
+    }
+
+    fn on_button(&mut self, _buttons: Buttons) {
+        self.button_pressed = true;
+    }
+}
+
+/// The screen with the time, battery and step count.
+pub fn draw_time<D: DrawTarget<Color = BinaryColor>>(
+    data: &FaceData,
+    target: &mut D,
+) -> Result<(), D::Error> {
+    Circle::new(Point::new(10, 10), 120)
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(target)?;
+
+    let mut t = ArrayString::<5>::new();
+    write!(&mut t, "{:02}:{:02}", data.time.hour(), data.time.minute()).unwrap();
+
+    Text::with_baseline(
+        t.as_str(),
+        Point::new(4, 200 - 20),
+        MonoTextStyle::new(&FONT_10X20, BinaryColor::On),
+        Baseline::Top,
+    )
+    .draw(target)?;
+
+    let right_aligned = TextStyleBuilder::new()
+        .alignment(Alignment::Right)
+        .baseline(Baseline::Top)
+        .build();
+
+    let mut battery = ArrayString::<4>::new();
+    write!(&mut battery, "{}%", data.battery_percentage.min(100)).unwrap();
+
+    Text::with_text_style(
+        battery.as_str(),
+        Point::new(200 - 4, 200 - 20),
+        MonoTextStyle::new(&FONT_10X20, BinaryColor::On),
+        right_aligned,
+    )
+    .draw(target)?;
+
+    let mut steps = ArrayString::<16>::new();
+    write!(&mut steps, "{} steps", data.steps).unwrap();
+
+    Text::with_text_style(
+        steps.as_str(),
+        Point::new(200 - 4, 200 - 34),
+        MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+        right_aligned,
+    )
+    .draw(target)?;
+
+    Ok(())
+}
+
+/// The screen asking to set the time, with the battery and step count.
+pub fn draw_set_time<D: DrawTarget<Color = BinaryColor>>(
+    data: &FaceData,
+    target: &mut D,
+) -> Result<(), D::Error> {
+    let centered = TextStyleBuilder::new()
+        .alignment(Alignment::Center)
+        .baseline(Baseline::Middle)
+        .build();
+
+    Text::with_text_style(
+        "Set the time",
+        Point::new(100, 90),
+        MonoTextStyle::new(&FONT_10X20, BinaryColor::On),
+        centered,
+    )
+    .draw(target)?;
+
+    Text::with_text_style(
+        "The clock stopped",
+        Point::new(100, 115),
+        MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+        centered,
+    )
+    .draw(target)?;
+
+    let mut status = ArrayString::<24>::new();
+    write!(
+        &mut status,
+        "{}% {} steps",
+        data.battery_percentage.min(100),
+        data.steps
+    )
+    .unwrap();
+
+    Text::with_text_style(
+        status.as_str(),
+        Point::new(100, 200 - 10),
+        MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+        centered,
+    )
+    .draw(target)?;
+
+    Ok(())
+}
+
+/// The screen shown when a button woke the watch up.
+pub fn draw_button_pressed<D: DrawTarget<Color = BinaryColor>>(
+    target: &mut D,
+) -> Result<(), D::Error> {
+    Rectangle::new(Point::new(10, 10), Size::new(180, 180))
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 2))
+        .draw(target)?;
+
+    Triangle::new(Point::new(0, 0), Point::new(5, 5), Point::new(0, 10))
+        .translate(Point::new(16, 18))
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(target)?;
+
+    Text::with_baseline(
+        "ayy lmao",
+        Point::new(24, 14),
+        MonoTextStyle::new(&FONT_9X18_BOLD, BinaryColor::On),
+        Baseline::Top,
+    )
+    .draw(target)?;
+
+    Ok(())
+}
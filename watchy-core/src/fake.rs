@@ -0,0 +1,185 @@
+//! In-memory stand-ins for the hardware, to run the application on the host.
+
+use time::Time;
+
+use crate::{
+    draw_buffer::DrawBuffer,
+    hal::{Accelerometer, Battery, ButtonInput, Buttons, Clock, DisplaySink, Haptics, Refresh},
+    refresh_policy::{RefreshPolicy, RefreshState},
+};
+
+/// Error returned by the parts of a [FakeWatch] that are set to fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FakeError;
+
+/// A watch that returns whatever it's set up with, and remembers what it
+/// was asked to do.
+pub struct FakeWatch {
+    pub time: Time,
+    pub voltage: f32,
+    pub temperature: Option<i16>,
+    pub acceleration: (u16, u16, u16),
+    pub steps: u32,
+    pub pressed: Buttons,
+    /// The clock stopped at some point, so [Self::time] is wrong.
+    pub time_lost: bool,
+
+    /// Make every display operation fail.
+    pub display_fails: bool,
+    /// Make every clock operation fail.
+    pub clock_fails: bool,
+
+    /// Time the alarm is set to.
+    pub alarm: Option<Time>,
+    pub accelerometer_initialized: bool,
+    pub draw_buffer: DrawBuffer,
+    pub refresh_policy: RefreshPolicy,
+    pub refresh_state: RefreshState,
+    /// What the display shows, after the last [DisplaySink::show].
+    pub shown: Option<DrawBuffer>,
+    /// How the display was last redrawn by [DisplaySink::show].
+    pub last_refresh: Option<Refresh>,
+    pub clear_count: u32,
+    pub show_count: u32,
+    pub vibration_count: u32,
+}
+
+impl FakeWatch {
+    pub fn new(time: Time) -> Self {
+        FakeWatch {
+            time,
+            voltage: 3.7,
+            temperature: Some(23),
+            acceleration: (0, 0, 0),
+            steps: 0,
+            pressed: Buttons::empty(),
+            time_lost: false,
+            display_fails: false,
+            clock_fails: false,
+            alarm: None,
+            accelerometer_initialized: false,
+            draw_buffer: DrawBuffer::empty(),
+            refresh_policy: RefreshPolicy::default(),
+            refresh_state: RefreshState::new(),
+            shown: None,
+            last_refresh: None,
+            clear_count: 0,
+            show_count: 0,
+            vibration_count: 0,
+        }
+    }
+}
+
+impl Clock for FakeWatch {
+    type Error = FakeError;
+
+    async fn time(&mut self) -> Result<Time, FakeError> {
+        if self.clock_fails {
+            return Err(FakeError);
+        }
+
+        Ok(self.time)
+    }
+
+    async fn time_lost(&mut self) -> Result<bool, FakeError> {
+        if self.clock_fails {
+            return Err(FakeError);
+        }
+
+        Ok(self.time_lost)
+    }
+
+    async fn set_alarm(&mut self, time: Time) -> Result<(), FakeError> {
+        if self.clock_fails {
+            return Err(FakeError);
+        }
+
+        self.alarm = Some(time);
+        Ok(())
+    }
+
+    async fn disable_alarm(&mut self) -> Result<(), FakeError> {
+        if self.clock_fails {
+            return Err(FakeError);
+        }
+
+        self.alarm = None;
+        Ok(())
+    }
+}
+
+impl DisplaySink for FakeWatch {
+    type Error = FakeError;
+
+    fn draw_buffer(&mut self) -> &mut DrawBuffer {
+        &mut self.draw_buffer
+    }
+
+    fn refresh_policy(&self) -> RefreshPolicy {
+        self.refresh_policy
+    }
+
+    fn refresh_state(&mut self) -> &mut RefreshState {
+        &mut self.refresh_state
+    }
+
+    async fn clear(&mut self) -> Result<(), FakeError> {
+        if self.display_fails {
+            return Err(FakeError);
+        }
+
+        self.shown = Some(DrawBuffer::empty());
+        self.clear_count += 1;
+        Ok(())
+    }
+
+    async fn show(&mut self, refresh: Refresh) -> Result<(), FakeError> {
+        if self.display_fails {
+            return Err(FakeError);
+        }
+
+        self.shown = Some(self.draw_buffer.clone());
+        self.last_refresh = Some(refresh);
+        self.show_count += 1;
+        Ok(())
+    }
+}
+
+impl Accelerometer for FakeWatch {
+    type Error = FakeError;
+
+    async fn initialize(&mut self) -> Result<(), FakeError> {
+        self.accelerometer_initialized = true;
+        Ok(())
+    }
+
+    async fn temperature_celsius(&mut self) -> Result<Option<i16>, FakeError> {
+        Ok(self.temperature)
+    }
+
+    async fn acceleration(&mut self) -> Result<(u16, u16, u16), FakeError> {
+        Ok(self.acceleration)
+    }
+
+    async fn step_count(&mut self) -> Result<u32, FakeError> {
+        Ok(self.steps)
+    }
+}
+
+impl Battery for FakeWatch {
+    async fn voltage(&mut self) -> f32 {
+        self.voltage
+    }
+}
+
+impl ButtonInput for FakeWatch {
+    fn pressed(&mut self) -> Buttons {
+        self.pressed
+    }
+}
+
+impl Haptics for FakeWatch {
+    async fn vibrate(&mut self, times: u8, _interval_ms: u32) {
+        self.vibration_count += times as u32;
+    }
+}
@@ -0,0 +1,403 @@
+//! Turns the raw state of the buttons into presses, long presses and
+//! combos.
+//!
+//! [GestureRecognizer] doesn't read the buttons or the clock itself. It's
+//! fed the buttons held down with the time in milliseconds whenever they
+//! change or [GestureRecognizer::next_deadline] passes, so it can be tested
+//! with made up timestamps.
+
+use crate::hal::Buttons;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonEvent {
+    /// A single button was pressed and released before it became a long
+    /// press.
+    Press(Buttons),
+
+    /// A single button was held down for [GestureConfig::long_press_ms].
+    LongPress(Buttons),
+
+    /// The button of a [ButtonEvent::LongPress] is still held down, sent
+    /// every [GestureConfig::repeat_interval_ms].
+    Repeat(Buttons),
+
+    /// Several buttons were held down together. Nothing else is sent until
+    /// they're all released.
+    Combo(Buttons),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GestureConfig {
+    /// How long the buttons have to stay the same before the change counts.
+    pub debounce_ms: u64,
+
+    /// How long a button has to be held down for a long press.
+    pub long_press_ms: u64,
+
+    /// Time between repeats while a long pressed button is held down.
+    pub repeat_interval_ms: u64,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        GestureConfig {
+            debounce_ms: 20,
+            long_press_ms: 600,
+            repeat_interval_ms: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Gesture {
+    /// No button held down.
+    Idle,
+
+    /// A single button held down since `since`.
+    Held { button: Buttons, since: u64 },
+
+    /// A single button held down for a long press.
+    LongHeld { button: Buttons, next_repeat: u64 },
+
+    /// Already reported as a combo, waiting for every button to be released.
+    Done,
+}
+
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    /// The buttons as last read, and since when.
+    raw: Buttons,
+    raw_since: u64,
+    /// The buttons after debouncing.
+    stable: Buttons,
+    gesture: Gesture,
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        GestureRecognizer {
+            config,
+            raw: Buttons::empty(),
+            raw_since: 0,
+            stable: Buttons::empty(),
+            gesture: Gesture::Idle,
+        }
+    }
+
+    /// The buttons held down after debouncing.
+    pub fn held(&self) -> Buttons {
+        self.stable
+    }
+
+    /// Take in the buttons held down at `now`, in milliseconds, and return
+    /// the event they complete, if any.
+    ///
+    /// At most one event is returned at a time, so call it again with the
+    /// same values after an event, until it returns `None`.
+    pub fn update(&mut self, now: u64, pressed: Buttons) -> Option<ButtonEvent> {
+        if pressed != self.raw {
+            self.raw = pressed;
+            self.raw_since = now;
+        }
+
+        if self.raw != self.stable && now.saturating_sub(self.raw_since) >= self.config.debounce_ms
+        {
+            self.stable = self.raw;
+            if let Some(event) = self.on_change(now) {
+                return Some(event);
+            }
+        }
+
+        self.on_time(now)
+    }
+
+    /// When [Self::update] should be called next if the buttons don't
+    /// change, or `None` if it only needs to be called when they do.
+    pub fn next_deadline(&self) -> Option<u64> {
+        let debounced = (self.raw != self.stable).then(|| self.raw_since + self.config.debounce_ms);
+        let gesture = match self.gesture {
+            Gesture::Held { since, .. } => Some(since + self.config.long_press_ms),
+            Gesture::LongHeld { next_repeat, .. } => Some(next_repeat),
+            Gesture::Idle | Gesture::Done => None,
+        };
+
+        match (debounced, gesture) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn on_change(&mut self, now: u64) -> Option<ButtonEvent> {
+        let previous = self.gesture;
+
+        if self.stable.is_empty() {
+            self.gesture = Gesture::Idle;
+            return match previous {
+                Gesture::Held { button, .. } => Some(ButtonEvent::Press(button)),
+                _ => None,
+            };
+        }
+
+        if self.stable.bits().count_ones() > 1 {
+            self.gesture = Gesture::Done;
+            return match previous {
+                Gesture::Done => None,
+                _ => Some(ButtonEvent::Combo(self.stable)),
+            };
+        }
+
+        match previous {
+            // Still the same button, as when the other buttons of a combo
+            // are released.
+            Gesture::Held { button, .. } | Gesture::LongHeld { button, .. }
+                if button == self.stable =>
+            {
+                None
+            }
+            Gesture::Done => None,
+            // Moved straight from one button to another, within the
+            // debounce time.
+            Gesture::Held { button, .. } => {
+                self.gesture = Gesture::Held {
+                    button: self.stable,
+                    since: now,
+                };
+                Some(ButtonEvent::Press(button))
+            }
+            Gesture::Idle | Gesture::LongHeld { .. } => {
+                self.gesture = Gesture::Held {
+                    button: self.stable,
+                    since: now,
+                };
+                None
+            }
+        }
+    }
+
+    fn on_time(&mut self, now: u64) -> Option<ButtonEvent> {
+        match self.gesture {
+            Gesture::Held { button, since } if now >= since + self.config.long_press_ms => {
+                self.gesture = Gesture::LongHeld {
+                    button,
+                    next_repeat: now + self.config.repeat_interval_ms,
+                };
+                Some(ButtonEvent::LongPress(button))
+            }
+            Gesture::LongHeld {
+                button,
+                next_repeat,
+            } if now >= next_repeat => {
+                self.gesture = Gesture::LongHeld {
+                    button,
+                    next_repeat: now + self.config.repeat_interval_ms,
+                };
+                Some(ButtonEvent::Repeat(button))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::{ButtonEvent, GestureConfig, GestureRecognizer};
+    use crate::hal::Buttons;
+
+    const A: Buttons = Buttons::TOP_LEFT;
+    const B: Buttons = Buttons::BOTTOM_RIGHT;
+
+    fn recognizer() -> GestureRecognizer {
+        GestureRecognizer::new(GestureConfig {
+            debounce_ms: 10,
+            long_press_ms: 500,
+            repeat_interval_ms: 100,
+        })
+    }
+
+    /// Feed the buttons read at each timestamp, and also call it at every
+    /// deadline in between, as whatever reads the buttons would.
+    fn run(
+        recognizer: &mut GestureRecognizer,
+        readings: &[(u64, Buttons)],
+    ) -> Vec<(u64, ButtonEvent)> {
+        let mut events = Vec::new();
+        let mut pressed = Buttons::empty();
+
+        let mut update = |recognizer: &mut GestureRecognizer, now, pressed| {
+            while let Some(event) = recognizer.update(now, pressed) {
+                events.push((now, event));
+            }
+        };
+
+        for &(time, buttons) in readings {
+            while let Some(deadline) = recognizer.next_deadline().filter(|&d| d < time) {
+                update(recognizer, deadline, pressed);
+            }
+            pressed = buttons;
+            update(recognizer, time, pressed);
+        }
+
+        events
+    }
+
+    #[test]
+    fn test_press() {
+        let mut recognizer = recognizer();
+        let events = run(
+            &mut recognizer,
+            &[
+                (1000, A),
+                (1100, Buttons::empty()),
+                (2000, Buttons::empty()),
+            ],
+        );
+        assert_eq!(events, [(1110, ButtonEvent::Press(A))]);
+        assert_eq!(recognizer.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_bounces_are_ignored() {
+        let mut recognizer = recognizer();
+        let events = run(
+            &mut recognizer,
+            &[
+                (1000, A),
+                (1002, Buttons::empty()),
+                (1004, A),
+                (1100, Buttons::empty()),
+                (1103, A),
+                (1105, Buttons::empty()),
+                (2000, Buttons::empty()),
+            ],
+        );
+        assert_eq!(events, [(1115, ButtonEvent::Press(A))]);
+    }
+
+    #[test]
+    fn test_glitch_is_ignored() {
+        let mut recognizer = recognizer();
+        let events = run(
+            &mut recognizer,
+            &[
+                (1000, A),
+                (1005, Buttons::empty()),
+                (2000, Buttons::empty()),
+            ],
+        );
+        assert_eq!(events, []);
+        assert_eq!(recognizer.held(), Buttons::empty());
+    }
+
+    #[test]
+    fn test_long_press_and_repeat() {
+        let mut recognizer = recognizer();
+        let events = run(
+            &mut recognizer,
+            &[
+                (1000, B),
+                (1750, Buttons::empty()),
+                (2000, Buttons::empty()),
+            ],
+        );
+        assert_eq!(
+            events,
+            [
+                (1510, ButtonEvent::LongPress(B)),
+                (1610, ButtonEvent::Repeat(B)),
+                (1710, ButtonEvent::Repeat(B)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_late_update_doesnt_burst_repeats() {
+        let mut recognizer = recognizer();
+        assert_eq!(recognizer.update(1000, B), None);
+        assert_eq!(recognizer.update(1010, B), None);
+        assert_eq!(recognizer.update(3000, B), Some(ButtonEvent::LongPress(B)));
+        assert_eq!(recognizer.update(3000, B), None);
+        assert_eq!(recognizer.next_deadline(), Some(3100));
+    }
+
+    #[test]
+    fn test_combo() {
+        let mut recognizer = recognizer();
+        let events = run(
+            &mut recognizer,
+            &[
+                (1000, A),
+                (1050, A | B),
+                // Held long enough for a long press, and released one by one.
+                (2000, B),
+                (2100, Buttons::empty()),
+                (3000, Buttons::empty()),
+            ],
+        );
+        assert_eq!(events, [(1060, ButtonEvent::Combo(A | B))]);
+    }
+
+    #[test]
+    fn test_simultaneous_combo() {
+        let mut recognizer = recognizer();
+        let events = run(
+            &mut recognizer,
+            &[
+                (1000, A | B),
+                (1100, Buttons::empty()),
+                (2000, Buttons::empty()),
+            ],
+        );
+        assert_eq!(events, [(1010, ButtonEvent::Combo(A | B))]);
+    }
+
+    #[test]
+    fn test_combo_during_long_press() {
+        let mut recognizer = recognizer();
+        let events = run(
+            &mut recognizer,
+            &[
+                (1000, A),
+                (1550, A | B),
+                (1700, Buttons::empty()),
+                (2000, Buttons::empty()),
+            ],
+        );
+        assert_eq!(
+            events,
+            [
+                (1510, ButtonEvent::LongPress(A)),
+                (1560, ButtonEvent::Combo(A | B)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_presses_in_a_row() {
+        let mut recognizer = recognizer();
+        let events = run(
+            &mut recognizer,
+            &[
+                (1000, A),
+                (1100, Buttons::empty()),
+                (1200, B),
+                (1300, Buttons::empty()),
+                (1400, A),
+                (1405, B),
+                (1500, Buttons::empty()),
+                (2000, Buttons::empty()),
+            ],
+        );
+        assert_eq!(
+            events,
+            [
+                (1110, ButtonEvent::Press(A)),
+                (1310, ButtonEvent::Press(B)),
+                (1510, ButtonEvent::Press(B)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! What the application needs from the hardware. The firmware implements
+//! these for the Watchy, and [crate::fake] implements them in memory so the
+//! application can run on the host.
+
+// Everything runs on a single threaded executor, so there's no need for
+// Send bounds on the futures.
+#![allow(async_fn_in_trait)]
+
+use bitflags::bitflags;
+
+use crate::{
+    draw_buffer::DrawBuffer,
+    refresh_policy::{RefreshPolicy, RefreshState},
+};
+
+bitflags! {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+    pub struct Buttons : u8 {
+        const TOP_LEFT = 1 << 0;
+        const TOP_RIGHT = 1 << 1;
+        const BOTTOM_LEFT = 1 << 2;
+        const BOTTOM_RIGHT = 1 << 3;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WakeupCause {
+    /// First boot or manual reset from serial monitor
+    Reset,
+
+    /// The external RTC told us to wake up
+    ExternalRtcAlarm,
+
+    /// One (or more?) of the buttons was pressed
+    ButtonPress(Buttons),
+
+    /// Woken up by something that isn't a wakeup source, which probably
+    /// shouldn't happen.
+    Unknown,
+}
+
+pub trait Clock {
+    type Error: core::fmt::Debug;
+
+    /// The time on the clock. It's wrong after [Self::time_lost], but it
+    /// still counts from wherever it started again.
+    async fn time(&mut self) -> Result<time::Time, Self::Error>;
+
+    /// Whether the clock stopped at some point since the time was set, for
+    /// example while the battery was swapped.
+    async fn time_lost(&mut self) -> Result<bool, Self::Error>;
+
+    /// Wake up the next time the clock reaches `time`, ignoring the seconds.
+    async fn set_alarm(&mut self, time: time::Time) -> Result<(), Self::Error>;
+
+    /// Stop waking up on the alarm.
+    async fn disable_alarm(&mut self) -> Result<(), Self::Error>;
+}
+
+/// How the display is redrawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refresh {
+    /// Flash the whole panel, which gets rid of ghosting.
+    Full,
+
+    /// Only drive the pixels that differ from the frame the display shows,
+    /// which it keeps in its own RAM, even across deep sleep.
+    Partial,
+}
+
+pub trait DisplaySink {
+    type Error: core::fmt::Debug;
+
+    /// What gets drawn by [Self::show].
+    fn draw_buffer(&mut self) -> &mut DrawBuffer;
+
+    /// When [crate::app::refresh_display] picks a full refresh.
+    fn refresh_policy(&self) -> RefreshPolicy {
+        RefreshPolicy::default()
+    }
+
+    /// What the display went through since its last full refresh, kept
+    /// across deep sleep. It's up to [crate::app::refresh_display] to keep
+    /// it up to date.
+    fn refresh_state(&mut self) -> &mut RefreshState;
+
+    /// Make the display white with a full refresh, leaving the draw buffer
+    /// as it is. A partial refresh can follow, comparing against white.
+    async fn clear(&mut self) -> Result<(), Self::Error>;
+
+    /// Draw the whole draw buffer to the display.
+    async fn show(&mut self, refresh: Refresh) -> Result<(), Self::Error>;
+}
+
+pub trait Accelerometer {
+    type Error: core::fmt::Debug;
+
+    /// Get the accelerometer ready after a power cycle.
+    async fn initialize(&mut self) -> Result<(), Self::Error>;
+
+    async fn temperature_celsius(&mut self) -> Result<Option<i16>, Self::Error>;
+
+    /// Raw acceleration along the x, y and z axes.
+    async fn acceleration(&mut self) -> Result<(u16, u16, u16), Self::Error>;
+
+    async fn step_count(&mut self) -> Result<u32, Self::Error>;
+}
+
+pub trait Battery {
+    async fn voltage(&mut self) -> f32;
+}
+
+pub trait ButtonInput {
+    /// The buttons held down right now.
+    fn pressed(&mut self) -> Buttons;
+}
+
+pub trait Haptics {
+    /// Vibrate `times` times, for `interval_ms` each with as long pauses in between.
+    async fn vibrate(&mut self, times: u8, interval_ms: u32);
+}
@@ -0,0 +1,13 @@
+//! The parts of the firmware that don't touch the ESP32, so they can also
+//! be built and tested on the host.
+
+#![no_std]
+
+pub mod app;
+pub mod draw_buffer;
+pub mod face;
+pub mod fake;
+pub mod gesture;
+pub mod hal;
+pub mod persist;
+pub mod refresh_policy;
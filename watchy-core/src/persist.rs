@@ -0,0 +1,387 @@
+//! State kept across deep sleep, in memory that isn't cleared on wakeup
+//! such as the ESP32's RTC slow memory.
+//!
+//! That memory holds garbage after a power loss, and state saved by an
+//! older firmware may not mean the same thing anymore, so a [Record] stores
+//! the state with a version and a checksum, and loading it falls back to the
+//! defaults when either doesn't match.
+
+/// State that can be saved into a [Record].
+pub trait Persist: Sized {
+    /// Change this whenever the encoding changes, so state saved by a
+    /// different firmware is dropped instead of misread.
+    const VERSION: u16;
+
+    fn save(&self, writer: &mut Writer<'_>) -> Result<(), Full>;
+
+    /// Read back what [Self::save] wrote, or `None` if it doesn't make sense.
+    fn restore(reader: &mut Reader<'_>) -> Option<Self>;
+}
+
+/// The state doesn't fit into the [Record].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Full;
+
+/// Appends little endian values to a byte buffer.
+pub struct Writer<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Writer<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Writer { buffer, len: 0 }
+    }
+
+    /// Number of bytes written so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> Result<(), Full> {
+        let end = self.len + bytes.len();
+        self.buffer
+            .get_mut(self.len..end)
+            .ok_or(Full)?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+
+    pub fn u8(&mut self, value: u8) -> Result<(), Full> {
+        self.bytes(&[value])
+    }
+
+    pub fn bool(&mut self, value: bool) -> Result<(), Full> {
+        self.u8(value as u8)
+    }
+
+    pub fn u16(&mut self, value: u16) -> Result<(), Full> {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn i16(&mut self, value: i16) -> Result<(), Full> {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn u32(&mut self, value: u32) -> Result<(), Full> {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn i32(&mut self, value: i32) -> Result<(), Full> {
+        self.bytes(&value.to_le_bytes())
+    }
+}
+
+/// Reads back what a [Writer] wrote, returning `None` past the end.
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    /// Whether everything was read.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (bytes, rest) = self.bytes.split_first_chunk()?;
+        self.bytes = rest;
+        Some(*bytes)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        self.bytes().map(|[value]| value)
+    }
+
+    pub fn bool(&mut self) -> Option<bool> {
+        match self.u8()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        self.bytes().map(u16::from_le_bytes)
+    }
+
+    pub fn i16(&mut self) -> Option<i16> {
+        self.bytes().map(i16::from_le_bytes)
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    pub fn i32(&mut self) -> Option<i32> {
+        self.bytes().map(i32::from_le_bytes)
+    }
+}
+
+/// Marks memory that was written by [Record::store] at some point.
+const MAGIC: u16 = 0x5754;
+
+/// Room for a [Persist] value of up to `N` bytes, meant to be placed in a
+/// `static` that survives deep sleep.
+///
+/// Every field is valid for any bit pattern, so the memory can be read as a
+/// record whatever it holds.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Record<const N: usize> {
+    magic: u16,
+    version: u16,
+    len: u32,
+    checksum: u32,
+    data: [u8; N],
+}
+
+impl<const N: usize> Record<N> {
+    /// An empty record, which loads as the defaults.
+    pub const fn new() -> Self {
+        Record {
+            magic: 0,
+            version: 0,
+            len: 0,
+            checksum: 0,
+            data: [0; N],
+        }
+    }
+
+    /// Save `value`, replacing what was stored before. The record is left
+    /// empty if it doesn't fit.
+    pub fn store<T: Persist>(&mut self, value: &T) -> Result<(), Full> {
+        self.clear();
+
+        let mut writer = Writer::new(&mut self.data);
+        value.save(&mut writer)?;
+        let len = writer.len();
+
+        self.version = T::VERSION;
+        self.len = len as u32;
+        self.checksum = self.compute_checksum(len);
+        self.magic = MAGIC;
+        Ok(())
+    }
+
+    /// The stored value, or `None` if nothing valid of this version was
+    /// stored.
+    pub fn load<T: Persist>(&self) -> Option<T> {
+        let len = self.len as usize;
+        if self.magic != MAGIC
+            || self.version != T::VERSION
+            || len > N
+            || self.checksum != self.compute_checksum(len)
+        {
+            return None;
+        }
+
+        let mut reader = Reader::new(&self.data[..len]);
+        let value = T::restore(&mut reader)?;
+        reader.is_empty().then_some(value)
+    }
+
+    /// The stored value, or the defaults if nothing valid was stored.
+    pub fn load_or_default<T: Persist + Default>(&self) -> T {
+        self.load().unwrap_or_default()
+    }
+
+    /// Forget what was stored.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    fn compute_checksum(&self, len: usize) -> u32 {
+        let mut crc = Crc32::new();
+        crc.update(&self.version.to_le_bytes());
+        crc.update(&self.len.to_le_bytes());
+        crc.update(&self.data[..len]);
+        crc.finish()
+    }
+}
+
+impl<const N: usize> Default for Record<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// CRC-32 as used by zlib. Computed bit by bit since there are only a
+/// few bytes to check, once per wakeup.
+pub(crate) struct Crc32(u32);
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Crc32(!0)
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u32;
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (0xEDB8_8320 & mask);
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Crc32, Full, Persist, Reader, Record, Writer};
+
+    #[derive(Debug, Default, PartialEq, Eq)]
+    struct State {
+        face: u8,
+        enabled: bool,
+        steps: u32,
+        offset: i16,
+    }
+
+    impl Persist for State {
+        const VERSION: u16 = 1;
+
+        fn save(&self, writer: &mut Writer<'_>) -> Result<(), Full> {
+            writer.u8(self.face)?;
+            writer.bool(self.enabled)?;
+            writer.u32(self.steps)?;
+            writer.i16(self.offset)
+        }
+
+        fn restore(reader: &mut Reader<'_>) -> Option<Self> {
+            Some(State {
+                face: reader.u8()?,
+                enabled: reader.bool()?,
+                steps: reader.u32()?,
+                offset: reader.i16()?,
+            })
+        }
+    }
+
+    /// Same encoding as [State], after a firmware update that changed it.
+    #[derive(Debug, PartialEq, Eq)]
+    struct NewState(State);
+
+    impl Persist for NewState {
+        const VERSION: u16 = 2;
+
+        fn save(&self, writer: &mut Writer<'_>) -> Result<(), Full> {
+            self.0.save(writer)
+        }
+
+        fn restore(reader: &mut Reader<'_>) -> Option<Self> {
+            State::restore(reader).map(NewState)
+        }
+    }
+
+    fn state() -> State {
+        State {
+            face: 2,
+            enabled: true,
+            steps: 123_456,
+            offset: -42,
+        }
+    }
+
+    #[test]
+    fn test_crc32() {
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut record = Record::<16>::new();
+        record.store(&state()).unwrap();
+        assert_eq!(record.load(), Some(state()));
+
+        // Storing again replaces the old value.
+        let other = State {
+            face: 3,
+            ..State::default()
+        };
+        record.store(&other).unwrap();
+        assert_eq!(record.load(), Some(other));
+    }
+
+    #[test]
+    fn test_empty_record_loads_defaults() {
+        let record = Record::<16>::new();
+        assert_eq!(record.load::<State>(), None);
+        assert_eq!(record.load_or_default::<State>(), State::default());
+    }
+
+    #[test]
+    fn test_corrupted_data_loads_defaults() {
+        let mut record = Record::<16>::new();
+        record.store(&state()).unwrap();
+        record.data[3] ^= 0x10;
+        assert_eq!(record.load_or_default::<State>(), State::default());
+
+        let mut record = Record::<16>::new();
+        record.store(&state()).unwrap();
+        record.len = 4;
+        assert_eq!(record.load::<State>(), None);
+
+        record.len = u32::MAX;
+        assert_eq!(record.load::<State>(), None);
+    }
+
+    #[test]
+    fn test_garbage_loads_defaults() {
+        let mut record = Record::<16>::new();
+        record.magic = 0xA5A5;
+        record.version = 0xA5A5;
+        record.len = 0xA5A5_A5A5;
+        record.checksum = 0xA5A5_A5A5;
+        record.data = [0xA5; 16];
+        assert_eq!(record.load::<State>(), None);
+    }
+
+    #[test]
+    fn test_other_version_loads_defaults() {
+        let mut record = Record::<16>::new();
+        record.store(&state()).unwrap();
+        assert_eq!(record.load::<NewState>(), None);
+
+        record.store(&NewState(state())).unwrap();
+        assert_eq!(record.load::<State>(), None);
+        assert_eq!(record.load(), Some(NewState(state())));
+    }
+
+    #[test]
+    fn test_invalid_value_loads_defaults() {
+        let mut record = Record::<16>::new();
+        record
+            .store(&State {
+                enabled: true,
+                ..State::default()
+            })
+            .unwrap();
+        // A bool that's neither 0 nor 1, with a valid checksum.
+        record.data[1] = 2;
+        record.checksum = record.compute_checksum(record.len as usize);
+        assert_eq!(record.load::<State>(), None);
+    }
+
+    #[test]
+    fn test_too_large_for_the_record() {
+        let mut record = Record::<4>::new();
+        assert_eq!(record.store(&state()), Err(Full));
+        assert_eq!(record.load::<State>(), None);
+    }
+}
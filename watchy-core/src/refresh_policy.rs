@@ -0,0 +1,289 @@
+use crate::{
+    hal::Refresh,
+    persist::{Full, Persist, Reader, Writer},
+};
+
+/// When to do a full refresh instead of a partial one. Partial refreshes
+/// are faster and don't flash, but leave a bit of ghosting behind every time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshPolicy {
+    /// Number of partial refreshes in a row before a full one is forced.
+    pub max_partial_refreshes: u16,
+
+    /// Force a full refresh when the hour changes.
+    pub full_refresh_every_hour: bool,
+
+    /// Force a full refresh when the temperature moved this many degrees
+    /// Celsius away from the one at the last full refresh, since the panel
+    /// responds differently.
+    pub max_temperature_change: u16,
+}
+
+impl Default for RefreshPolicy {
+    fn default() -> Self {
+        RefreshPolicy {
+            max_partial_refreshes: 30,
+            full_refresh_every_hour: true,
+            max_temperature_change: 5,
+        }
+    }
+}
+
+const UNKNOWN_HOUR: u8 = u8::MAX;
+const UNKNOWN_TEMPERATURE: i16 = i16::MIN;
+
+/// What happened since the last full refresh. Kept across deep sleep with
+/// [Persist].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshState {
+    partial_refreshes: u16,
+    last_hour: u8,
+    full_refresh_temperature: i16,
+    /// [DrawBuffer::checksum](crate::draw_buffer::DrawBuffer::checksum) of
+    /// the frame on the display, or `None` if it shows something else.
+    frame: Option<u32>,
+}
+
+impl RefreshState {
+    pub const fn new() -> Self {
+        RefreshState {
+            partial_refreshes: 0,
+            last_hour: UNKNOWN_HOUR,
+            full_refresh_temperature: UNKNOWN_TEMPERATURE,
+            frame: None,
+        }
+    }
+
+    /// Number of partial refreshes since the last full one.
+    pub fn partial_refreshes(&self) -> u16 {
+        self.partial_refreshes
+    }
+
+    /// Whether the next refresh should be a full one, given the current hour
+    /// and temperature if they could be read.
+    pub fn needs_full_refresh(
+        &self,
+        policy: &RefreshPolicy,
+        hour: Option<u8>,
+        temperature: Option<i16>,
+    ) -> bool {
+        if self.partial_refreshes >= policy.max_partial_refreshes {
+            return true;
+        }
+
+        if let Some(hour) = hour {
+            if policy.full_refresh_every_hour
+                && self.last_hour != UNKNOWN_HOUR
+                && self.last_hour != hour
+            {
+                return true;
+            }
+        }
+
+        if let Some(temperature) = temperature {
+            if self.full_refresh_temperature != UNKNOWN_TEMPERATURE
+                && temperature.abs_diff(self.full_refresh_temperature)
+                    > policy.max_temperature_change
+            {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// How to show the frame with checksum `frame`, or `None` if the display
+    /// already shows it.
+    ///
+    /// A partial refresh needs the display to hold the frame it shows, so
+    /// it's only picked when that's known to be a frame that was drawn.
+    pub fn next_refresh(
+        &self,
+        policy: &RefreshPolicy,
+        hour: Option<u8>,
+        temperature: Option<i16>,
+        frame: u32,
+    ) -> Option<Refresh> {
+        match self.frame {
+            Some(shown) if shown == frame => None,
+            Some(_) if !self.needs_full_refresh(policy, hour, temperature) => {
+                Some(Refresh::Partial)
+            }
+            _ => Some(Refresh::Full),
+        }
+    }
+
+    /// Remember the checksum of the frame on the display, or `None` after
+    /// drawing anything else, like a grayscale image.
+    pub fn record_frame(&mut self, frame: Option<u32>) {
+        self.frame = frame;
+    }
+
+    pub fn record_full_refresh(&mut self, hour: Option<u8>, temperature: Option<i16>) {
+        self.partial_refreshes = 0;
+        self.last_hour = hour.unwrap_or(UNKNOWN_HOUR);
+        self.full_refresh_temperature = temperature.unwrap_or(UNKNOWN_TEMPERATURE);
+    }
+
+    pub fn record_partial_refresh(&mut self, hour: Option<u8>) {
+        self.partial_refreshes = self.partial_refreshes.saturating_add(1);
+        if let Some(hour) = hour {
+            self.last_hour = hour;
+        }
+    }
+}
+
+impl Default for RefreshState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Persist for RefreshState {
+    const VERSION: u16 = 2;
+
+    fn save(&self, writer: &mut Writer<'_>) -> Result<(), Full> {
+        writer.u16(self.partial_refreshes)?;
+        writer.u8(self.last_hour)?;
+        writer.i16(self.full_refresh_temperature)?;
+        writer.bool(self.frame.is_some())?;
+        writer.u32(self.frame.unwrap_or(0))
+    }
+
+    fn restore(reader: &mut Reader<'_>) -> Option<Self> {
+        let state = RefreshState {
+            partial_refreshes: reader.u16()?,
+            last_hour: reader.u8()?,
+            full_refresh_temperature: reader.i16()?,
+            frame: {
+                let known = reader.bool()?;
+                let frame = reader.u32()?;
+                known.then_some(frame)
+            },
+        };
+
+        (state.last_hour < 24 || state.last_hour == UNKNOWN_HOUR).then_some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RefreshPolicy, RefreshState};
+    use crate::{hal::Refresh, persist::Record};
+
+    #[test]
+    fn test_new_state_allows_partial_refresh() {
+        let state = RefreshState::new();
+        assert!(!state.needs_full_refresh(&RefreshPolicy::default(), Some(12), Some(20)));
+    }
+
+    #[test]
+    fn test_full_refresh_after_max_partial_refreshes() {
+        let policy = RefreshPolicy {
+            max_partial_refreshes: 3,
+            ..Default::default()
+        };
+        let mut state = RefreshState::new();
+        state.record_full_refresh(Some(12), Some(20));
+
+        for _ in 0..3 {
+            assert!(!state.needs_full_refresh(&policy, Some(12), Some(20)));
+            state.record_partial_refresh(Some(12));
+        }
+        assert_eq!(state.partial_refreshes(), 3);
+        assert!(state.needs_full_refresh(&policy, Some(12), Some(20)));
+
+        state.record_full_refresh(Some(12), Some(20));
+        assert!(!state.needs_full_refresh(&policy, Some(12), Some(20)));
+    }
+
+    #[test]
+    fn test_full_refresh_when_hour_changes() {
+        let mut state = RefreshState::new();
+        state.record_full_refresh(Some(11), Some(20));
+        state.record_partial_refresh(Some(11));
+
+        let policy = RefreshPolicy::default();
+        assert!(!state.needs_full_refresh(&policy, Some(11), Some(20)));
+        assert!(state.needs_full_refresh(&policy, Some(12), Some(20)));
+        // Not knowing the time shouldn't force anything.
+        assert!(!state.needs_full_refresh(&policy, None, Some(20)));
+
+        let policy = RefreshPolicy {
+            full_refresh_every_hour: false,
+            ..Default::default()
+        };
+        assert!(!state.needs_full_refresh(&policy, Some(12), Some(20)));
+    }
+
+    #[test]
+    fn test_full_refresh_when_temperature_changes() {
+        let policy = RefreshPolicy {
+            max_temperature_change: 5,
+            ..Default::default()
+        };
+        let mut state = RefreshState::new();
+        state.record_full_refresh(Some(12), Some(20));
+
+        assert!(!state.needs_full_refresh(&policy, Some(12), Some(25)));
+        assert!(!state.needs_full_refresh(&policy, Some(12), Some(15)));
+        assert!(state.needs_full_refresh(&policy, Some(12), Some(26)));
+        assert!(state.needs_full_refresh(&policy, Some(12), Some(14)));
+        assert!(!state.needs_full_refresh(&policy, Some(12), None));
+
+        state.record_full_refresh(Some(12), None);
+        assert!(!state.needs_full_refresh(&policy, Some(12), Some(-40)));
+    }
+
+    #[test]
+    fn test_next_refresh() {
+        let policy = RefreshPolicy {
+            max_partial_refreshes: 1,
+            ..Default::default()
+        };
+        let mut state = RefreshState::new();
+        // Nothing is known about what the display shows.
+        assert_eq!(
+            state.next_refresh(&policy, Some(12), Some(20), 1),
+            Some(Refresh::Full)
+        );
+
+        state.record_full_refresh(Some(12), Some(20));
+        state.record_frame(Some(1));
+        assert_eq!(state.next_refresh(&policy, Some(12), Some(20), 1), None);
+        assert_eq!(
+            state.next_refresh(&policy, Some(12), Some(20), 2),
+            Some(Refresh::Partial)
+        );
+
+        state.record_partial_refresh(Some(12));
+        state.record_frame(Some(2));
+        assert_eq!(
+            state.next_refresh(&policy, Some(12), Some(20), 3),
+            Some(Refresh::Full)
+        );
+        // Even when a full refresh is due, an unchanged frame isn't redrawn.
+        assert_eq!(state.next_refresh(&policy, Some(13), Some(20), 2), None);
+
+        state.record_frame(None);
+        assert_eq!(
+            state.next_refresh(&RefreshPolicy::default(), Some(12), Some(20), 2),
+            Some(Refresh::Full)
+        );
+    }
+
+    #[test]
+    fn test_persist() {
+        let mut state = RefreshState::new();
+        state.record_full_refresh(Some(12), Some(-3));
+        state.record_partial_refresh(Some(13));
+        state.record_frame(Some(0xDEAD_BEEF));
+
+        let mut record = Record::<16>::new();
+        record.store(&state).unwrap();
+        assert_eq!(record.load(), Some(state));
+
+        record.store(&RefreshState::new()).unwrap();
+        assert_eq!(record.load(), Some(RefreshState::new()));
+    }
+}